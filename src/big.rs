@@ -0,0 +1,350 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+use std::str::FromStr;
+
+const LIMB: u64 = 1_000_000_000;
+
+/// Arbitrary-precision unsigned integer, for answers that outgrow `u128`.
+/// Stored as base 10^9 limbs, least significant first, without leading zero
+/// limbs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Builds a number from its decimal digits, most significant first.
+    pub fn from_digits(digits: &[u8]) -> Self {
+        let limbs = digits
+            .rchunks(9)
+            .map(|chunk| chunk.iter().fold(0, |acc, &d| acc * 10 + d as u32))
+            .collect();
+        BigUint { limbs }.normalized()
+    }
+
+    /// The value as a `u128`, if it fits.
+    pub fn to_u128(&self) -> Option<u128> {
+        self.limbs.iter().rev().try_fold(0u128, |acc, &limb| {
+            acc.checked_mul(LIMB as u128)?.checked_add(limb as u128)
+        })
+    }
+
+    /// The quotient and remainder of dividing by `divisor`, or `None` when
+    /// it is zero.
+    pub fn div_rem(&self, divisor: &BigUint) -> Option<(BigUint, BigUint)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut remainder = BigUint::zero();
+        for (i, &limb) in self.limbs.iter().enumerate().rev() {
+            remainder.limbs.insert(0, limb);
+            remainder = remainder.normalized();
+            // the largest digit q with divisor * q <= remainder
+            let (mut low, mut high) = (0, LIMB as u32 - 1);
+            while low < high {
+                let mid = high - (high - low) / 2;
+                if divisor * &BigUint::from(mid as u64) <= remainder {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+            remainder -= &(divisor * &BigUint::from(low as u64));
+            quotient[i] = low;
+        }
+        Some((BigUint { limbs: quotient }.normalized(), remainder))
+    }
+
+    pub fn pow(&self, mut exp: u32) -> BigUint {
+        let mut result = BigUint::from(1u64);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = &result * &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    /// How many base 10^9 limbs the number takes, a measure of its size.
+    pub fn limb_count(&self) -> usize {
+        self.limbs.len()
+    }
+
+    /// The base 10 logarithm, about how many digits the number has; the
+    /// number is not zero.
+    pub fn log10(&self) -> f64 {
+        let (top, rest) = self.limbs.split_last().expect("log10 of zero");
+        f64::from(*top).log10() + 9.0 * rest.len() as f64
+    }
+
+    fn normalized(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(mut value: u128) -> Self {
+        let mut limbs = Vec::new();
+        while value > 0 {
+            limbs.push((value % LIMB as u128) as u32);
+            value /= LIMB as u128;
+        }
+        BigUint { limbs }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        BigUint::from(value as u128)
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, other: &BigUint) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+        let mut carry = 0;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let sum = *limb as u64 + other.limbs.get(i).copied().unwrap_or(0) as u64 + carry;
+            *limb = (sum % LIMB) as u32;
+            carry = sum / LIMB;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+}
+
+impl Add<&BigUint> for BigUint {
+    type Output = BigUint;
+
+    fn add(mut self, other: &BigUint) -> BigUint {
+        self += other;
+        self
+    }
+}
+
+impl Add for BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: BigUint) -> BigUint {
+        self + &other
+    }
+}
+
+impl SubAssign<&BigUint> for BigUint {
+    /// Panics if `other` is larger, like the unsigned primitives in debug
+    /// builds.
+    fn sub_assign(&mut self, other: &BigUint) {
+        assert!(*self >= *other, "attempt to subtract with overflow");
+        let mut borrow = 0;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let sub = other.limbs.get(i).copied().unwrap_or(0) as u64 + borrow;
+            if (*limb as u64) < sub {
+                *limb = (*limb as u64 + LIMB - sub) as u32;
+                borrow = 1;
+            } else {
+                *limb = (*limb as u64 - sub) as u32;
+                borrow = 0;
+            }
+        }
+        *self = std::mem::take(self).normalized();
+    }
+}
+
+impl Sub<&BigUint> for BigUint {
+    type Output = BigUint;
+
+    fn sub(mut self, other: &BigUint) -> BigUint {
+        self -= other;
+        self
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+        let mut limbs = vec![0u64; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let cur = limbs[i + j] + a as u64 * b as u64 + carry;
+                limbs[i + j] = cur % LIMB;
+                carry = cur / LIMB;
+            }
+            limbs[i + other.limbs.len()] += carry;
+        }
+        BigUint {
+            limbs: limbs.into_iter().map(|l| l as u32).collect(),
+        }
+        .normalized()
+    }
+}
+
+impl Mul for BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: BigUint) -> BigUint {
+        &self * &other
+    }
+}
+
+impl Sum for BigUint {
+    fn sum<I: Iterator<Item = BigUint>>(iter: I) -> Self {
+        iter.fold(BigUint::zero(), |acc, n| acc + n)
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.limbs.split_last() {
+            None => write!(f, "0"),
+            Some((most, rest)) => {
+                write!(f, "{}", most)?;
+                rest.iter()
+                    .rev()
+                    .try_for_each(|limb| write!(f, "{:09}", limb))
+            }
+        }
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("not an unsigned integer: {}", s));
+        }
+        let digits: Vec<u8> = s.bytes().map(|b| b - b'0').collect();
+        Ok(BigUint::from_digits(&digits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(big("0").to_string(), "0");
+        assert_eq!(big("000123").to_string(), "123");
+        assert_eq!(
+            big("1234567890123456789012345").to_string(),
+            "1234567890123456789012345"
+        );
+        assert!("12a".parse::<BigUint>().is_err());
+        assert!("".parse::<BigUint>().is_err());
+    }
+
+    #[test]
+    fn add_and_mul() {
+        let a = BigUint::from(u128::MAX);
+        assert_eq!(
+            (a.clone() + BigUint::from(1u64)).to_string(),
+            "340282366920938463463374607431768211456"
+        );
+        assert_eq!(
+            (a.clone() * a).to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025"
+        );
+        assert_eq!((big("999999999") * BigUint::zero()), BigUint::zero());
+    }
+
+    #[test]
+    fn sub() {
+        let a = BigUint::from(u128::MAX) + BigUint::from(1u64);
+        assert_eq!((a.clone() - &BigUint::from(1u64)), BigUint::from(u128::MAX));
+        assert_eq!(
+            (big("1000000000000000000") - &big("1")).to_string(),
+            "999999999999999999"
+        );
+        assert!((a.clone() - &a).is_zero());
+    }
+
+    #[test]
+    fn div_rem_and_pow() {
+        let a = big("123456789012345678901234567890");
+        let b = big("987654321987");
+        let (q, r) = a.div_rem(&b).unwrap();
+        assert_eq!(q.to_string(), "124999998748520313");
+        assert_eq!(r.to_string(), "645722545959");
+        assert_eq!(&q * &b + &r, a);
+        assert_eq!(a.div_rem(&a), Some((big("1"), BigUint::zero())));
+        assert_eq!(b.div_rem(&a), Some((BigUint::zero(), b.clone())));
+        assert_eq!(a.div_rem(&BigUint::zero()), None);
+
+        assert_eq!(
+            big("2").pow(128),
+            BigUint::from(u128::MAX) + BigUint::from(1u64)
+        );
+        assert_eq!(big("12345").pow(0), big("1"));
+        assert_eq!(BigUint::zero().pow(3), BigUint::zero());
+        assert_eq!(big("1000000000").pow(3).limb_count(), 4);
+        assert_eq!(big("1000000000").pow(3).log10(), 27.0);
+        assert!((big("2").pow(100).log10() - 30.103).abs() < 1e-3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sub_underflow() {
+        let _ = big("999999999") - &big("1000000000");
+    }
+
+    #[test]
+    fn compare_and_convert() {
+        assert!(big("1000000000") > big("999999999"));
+        assert!(big("123456789123") < big("123456789124"));
+        assert_eq!(
+            big("340282366920938463463374607431768211455").to_u128(),
+            Some(u128::MAX)
+        );
+        assert_eq!(
+            big("340282366920938463463374607431768211456").to_u128(),
+            None
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::AocResult;
+
+/// `--name value` and `--flag` arguments given after the day and command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Options {
+    values: BTreeMap<String, Option<String>>,
+}
+
+impl Options {
+    /// Parses `--name value` pairs and bare `--flag`s. A `--name` followed by
+    /// another `--name` (or by nothing) is a flag.
+    pub fn parse<I, S>(args: I) -> AocResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut values = BTreeMap::new();
+        let mut args = args.into_iter().map(Into::into).peekable();
+
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unexpected argument: {}", arg))?
+                .to_string();
+            let value = args.next_if(|next| !next.starts_with("--"));
+            values.insert(name, value);
+        }
+
+        Ok(Options { values })
+    }
+
+    /// Fails on the first option not in `names`, so that a misspelt option
+    /// is not silently ignored.
+    pub fn allow(&self, names: &[&str]) -> AocResult<()> {
+        let Some(unknown) = self.values.keys().find(|k| !names.contains(&k.as_str())) else {
+            return Ok(());
+        };
+        let expected = if names.is_empty() {
+            "this command takes no options".to_string()
+        } else {
+            let names: Vec<String> = names.iter().map(|n| format!("--{}", n)).collect();
+            format!("expected {}", names.join(", "))
+        };
+        Err(format!("unknown option --{} ({})", unknown, expected).into())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).and_then(|v| v.as_deref())
+    }
+
+    pub fn get<T>(&self, name: &str) -> AocResult<Option<T>>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        match self.values.get(name) {
+            None => Ok(None),
+            Some(None) => Err(format!("--{} needs a value", name).into()),
+            Some(Some(v)) => v
+                .parse()
+                .map(Some)
+                .map_err(|e| format!("invalid value for --{}: {} ({})", name, v, e).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_values_and_flags() {
+        let options = Options::parse(["--base", "16", "--lenient", "--k", "3"]).unwrap();
+        assert_eq!(options.get::<u32>("base").unwrap(), Some(16));
+        assert_eq!(options.get::<u32>("k").unwrap(), Some(3));
+        assert!(options.flag("lenient"));
+        assert!(!options.flag("missing"));
+        assert_eq!(options.get::<u32>("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn allow_rejects_unknown_options() {
+        let options = Options::parse(["--exactley", "3", "--base", "16"]).unwrap();
+        assert!(options.allow(&["exactley", "base"]).is_ok());
+        assert_eq!(
+            options.allow(&["exactly", "base"]).unwrap_err().to_string(),
+            "unknown option --exactley (expected --exactly, --base)"
+        );
+        assert_eq!(
+            options.allow(&[]).unwrap_err().to_string(),
+            "unknown option --base (this command takes no options)"
+        );
+        assert!(Options::default().allow(&[]).is_ok());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Options::parse(["16"]).is_err());
+        let options = Options::parse(["--base", "x", "--k"]).unwrap();
+        assert!(options.get::<u32>("base").is_err());
+        assert!(options.get::<u32>("k").is_err());
+    }
+}
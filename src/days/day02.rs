@@ -1,89 +1,363 @@
-use crate::{AocResult, Day};
+use crate::error::ParseError;
+use crate::{AocResult, Day, Options};
+use std::io::Write;
+use std::ops::RangeInclusive;
 
 pub struct Day02;
 impl Day02 {
-
-    fn solve_with<F>(&self, input: &str, is_invalid: F) -> AocResult<String>
-    where
-        F: Fn(u64) -> bool,
-    {
-        let sequences: Vec<Vec<u64>> = input
-            .split(',')
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .map(|s| {
-                let mut it = s.split('-').map(|n| n.parse::<u64>().unwrap());
-                let start = it.next().unwrap();
-                let end = it.next().unwrap();
-                (start..=end).collect()
-            })
-            .collect();
-
-        let sum: u64 = sequences
+    fn solve_with(
+        &self,
+        ranges: &[RangeInclusive<u64>],
+        rule: RepetitionRule,
+    ) -> AocResult<String> {
+        // no more than 2^64 IDs below 2^64 each, so the sum fits in a u128
+        let sum: u128 = ranges
             .iter()
-            .flat_map(|seq| seq.iter())
-            .copied()
-            .filter(|n| is_invalid(*n))
+            .flat_map(|range| rule.invalid_ids(range.clone()))
+            .map(|invalid| invalid.id as u128)
             .sum();
 
         Ok(sum.to_string())
     }
 
+    /// Prints, for every range, the invalid IDs it contains and the block
+    /// that repeats in each of them.
+    fn explain(
+        &self,
+        ranges: &[RangeInclusive<u64>],
+        rule: RepetitionRule,
+        out: &mut dyn Write,
+    ) -> AocResult<()> {
+        let mut total = 0u128;
+        for range in ranges {
+            let invalid: Vec<_> = rule.invalid_ids(range.clone()).collect();
+            total += invalid.iter().map(|i| i.id as u128).sum::<u128>();
 
-    fn is_invalid_number_p1(n: u64) -> bool {
-        let as_str = n.to_string();
-        let to: usize = as_str.len() / 2;
-        for i in 1..=to {
-            let (a, b) = as_str.split_at(i);
-            if a == b {
-                println!("{} IS INVALID cause {} == {}", as_str, a, b);
-                return true;
+            let described: Vec<_> = invalid
+                .iter()
+                .map(|i| {
+                    format!(
+                        "{} ({} x {})",
+                        to_base(i.id, rule.base),
+                        to_base(i.block, rule.base),
+                        i.times
+                    )
+                })
+                .collect();
+            let range = format!("{}-{}", range.start(), range.end());
+            match described.as_slice() {
+                [] => writeln!(out, "{} contains no invalid IDs.", range)?,
+                [one] => writeln!(out, "{} has one invalid ID, {}.", range, one)?,
+                [init @ .., last] => writeln!(
+                    out,
+                    "{} has {} invalid IDs, {} and {}.",
+                    range,
+                    described.len(),
+                    init.join(", "),
+                    last
+                )?,
             }
         }
-        false
+        writeln!(out, "total: {}", total)?;
+        Ok(())
+    }
+}
+impl Day for Day02 {
+    fn name(&self) -> &'static str {
+        "day02"
     }
 
-    fn is_invalid_number_p2(n: u64) -> bool {
-        Day02::is_invalid_number_p1(n) || Day02::is_number_duplicated_n_times(n)
+    fn part1(&self, input: &str) -> AocResult<String> {
+        self.solve_with(&parse_input(input)?, RepetitionRule::PART1)
     }
 
-    fn is_number_duplicated_n_times(n: u64) -> bool {
-        let as_str = n.to_string();
-        let to: usize = as_str.len() / 2;
-        for i in 1..=to {
-            let (a, b) = as_str.split_at(i);
-            let c = Day02::split_by_len(b, a.len());
-            if c.iter().filter(|s| s.as_str() != a).count() == 0 {
-                println!("{} IS INVALID cause {} is in {}", as_str, a, b);
-                return true;
+    fn part2(&self, input: &str) -> AocResult<String> {
+        self.solve_with(&parse_input(input)?, RepetitionRule::PART2)
+    }
+
+    fn run(
+        &self,
+        command: &str,
+        input: &str,
+        options: &Options,
+        out: &mut dyn Write,
+    ) -> AocResult<()> {
+        if !matches!(command, "rule" | "explain") {
+            return Err(format!(
+                "day02 has no command '{}' (available: rule, explain)",
+                command
+            )
+            .into());
+        }
+        let mut allowed = RepetitionRule::OPTIONS.to_vec();
+        allowed.push("lenient");
+        options.allow(&allowed)?;
+        let rule = RepetitionRule::from_options(options)?;
+        let ranges = if options.flag("lenient") {
+            let (ranges, skipped) = parse_input_lenient(input);
+            for error in skipped {
+                eprintln!("skipped {}", error);
             }
+            ranges
+        } else {
+            parse_input(input)?
+        };
+
+        if command == "rule" {
+            writeln!(out, "{}", self.solve_with(&ranges, rule)?)?;
+            Ok(())
+        } else {
+            self.explain(&ranges, rule, out)
         }
-        false
     }
+}
+
+/// Ranges are `start-end` entries separated by commas, whitespace or newlines.
+fn parse_input(input: &str) -> Result<Vec<RangeInclusive<u64>>, ParseError> {
+    entries(input).collect()
+}
+
+/// Like `parse_input`, but skips malformed entries and returns them apart.
+fn parse_input_lenient(input: &str) -> (Vec<RangeInclusive<u64>>, Vec<ParseError>) {
+    let (ranges, errors): (Vec<_>, Vec<_>) = entries(input).partition(Result::is_ok);
+    (
+        ranges.into_iter().flatten().collect(),
+        errors.into_iter().filter_map(Result::err).collect(),
+    )
+}
+
+fn entries(input: &str) -> impl Iterator<Item = Result<RangeInclusive<u64>, ParseError>> + '_ {
+    input.lines().enumerate().flat_map(|(row, line)| {
+        let mut tokens = Vec::new();
+        let mut start = None;
+        for (i, c) in line.char_indices().chain([(line.len(), ',')]) {
+            match (start, c == ',' || c.is_whitespace()) {
+                (None, false) => start = Some(i),
+                (Some(s), true) => {
+                    tokens.push((s, &line[s..i]));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
 
-    fn split_by_len(s: &str, len: usize) -> Vec<String> {
-        assert!(len > 0);
+        tokens.into_iter().map(move |(offset, text)| {
+            parse_range(text).map_err(|reason| ParseError::new(row, line, offset, text, reason))
+        })
+    })
+}
 
-        s.chars()
-            .collect::<Vec<_>>()
-            .chunks(len)
-            .map(|chunk| chunk.iter().collect())
-            .collect()
+fn parse_range(text: &str) -> Result<RangeInclusive<u64>, &'static str> {
+    let (start, end) = text.split_once('-').ok_or("missing '-'")?;
+    if end.contains('-') {
+        return Err("too many '-'");
     }
+    let number = |s: &str, missing| match s {
+        "" => Err(missing),
+        s => s.parse::<u64>().map_err(|_| "not a number"),
+    };
+    let start = number(start, "missing start")?;
+    let end = number(end, "missing end")?;
+    if start > end {
+        return Err("start is after end");
+    }
+    Ok(start..=end)
 }
-impl Day for Day02 {
-    fn name(&self) -> &'static str {
-        "day02"
+
+/// How many times the block has to repeat for an ID to be invalid. A block
+/// always repeats at least twice, whatever the bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repetitions {
+    Exactly(u32),
+    AtLeast(u32),
+    AtMost(u32),
+}
+impl Repetitions {
+    fn accepts(&self, times: u32) -> bool {
+        match *self {
+            Repetitions::Exactly(k) => times == k,
+            Repetitions::AtLeast(k) => times >= k,
+            Repetitions::AtMost(k) => times <= k,
+        }
     }
+}
 
-    fn part1(&self, input: &str) -> AocResult<String> {
-        self.solve_with(input, Day02::is_invalid_number_p1)
+/// An ID is invalid when its digits in `base` are a block of at least
+/// `min_block_len` digits repeated a number of times accepted by `repetitions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepetitionRule {
+    repetitions: Repetitions,
+    min_block_len: u32,
+    base: u32,
+}
+impl RepetitionRule {
+    /// The pattern repeats exactly twice.
+    pub const PART1: RepetitionRule = RepetitionRule {
+        repetitions: Repetitions::Exactly(2),
+        min_block_len: 1,
+        base: 10,
+    };
+    /// The pattern repeats at least twice.
+    pub const PART2: RepetitionRule = RepetitionRule {
+        repetitions: Repetitions::AtLeast(2),
+        min_block_len: 1,
+        base: 10,
+    };
+
+    pub fn new(repetitions: Repetitions, min_block_len: u32, base: u32) -> AocResult<Self> {
+        let k = match repetitions {
+            Repetitions::Exactly(k) | Repetitions::AtLeast(k) | Repetitions::AtMost(k) => k,
+        };
+        if k < 2 {
+            return Err(format!("a block must repeat at least twice, got {}", k).into());
+        }
+        if min_block_len == 0 {
+            return Err("minimum block length must be at least 1".into());
+        }
+        if !(2..=36).contains(&base) {
+            return Err(format!("base must be between 2 and 36, got {}", base).into());
+        }
+        Ok(RepetitionRule {
+            repetitions,
+            min_block_len,
+            base,
+        })
     }
 
-    fn part2(&self, input: &str) -> AocResult<String> {
-        self.solve_with(input, Day02::is_invalid_number_p2)
+    /// The options `from_options` reads.
+    pub const OPTIONS: [&'static str; 5] = ["exactly", "at-least", "at-most", "min-block", "base"];
+
+    /// Builds a rule from `--exactly k`, `--at-least k` or `--at-most k`, plus
+    /// the optional `--min-block n` and `--base b`.
+    pub fn from_options(options: &Options) -> AocResult<Self> {
+        let bounds = [
+            options.get("exactly")?.map(Repetitions::Exactly),
+            options.get("at-least")?.map(Repetitions::AtLeast),
+            options.get("at-most")?.map(Repetitions::AtMost),
+        ];
+        let mut given = bounds.into_iter().flatten();
+        let repetitions = match (given.next(), given.next()) {
+            (Some(r), None) => r,
+            (None, _) => Repetitions::AtLeast(2),
+            (Some(_), Some(_)) => {
+                return Err("use only one of --exactly, --at-least and --at-most".into());
+            }
+        };
+
+        RepetitionRule::new(
+            repetitions,
+            options.get("min-block")?.unwrap_or(1),
+            options.get("base")?.unwrap_or(10),
+        )
+    }
+
+    pub fn matches(&self, n: u64) -> bool {
+        self.shapes(digit_count(n as u128, self.base))
+            .any(|(block_len, times)| {
+                (n as u128).is_multiple_of(repunit(self.base, block_len, times))
+            })
+    }
+
+    /// The invalid IDs in `range`, in increasing order.
+    pub fn invalid_ids(&self, range: RangeInclusive<u64>) -> InvalidIds {
+        InvalidIds {
+            rule: *self,
+            next: *range.start() as u128,
+            end: *range.end() as u128,
+        }
+    }
+
+    /// The `(block_len, times)` splits of a `len`-digit ID allowed by the rule,
+    /// shortest block first.
+    fn shapes(&self, len: u32) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (2..=len)
+            .rev()
+            .filter(move |&times| len.is_multiple_of(times) && self.repetitions.accepts(times))
+            .map(move |times| (len / times, times))
+            .filter(|&(block_len, _)| block_len >= self.min_block_len)
     }
 }
+
+/// An invalid ID: `block` written `times` times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidId {
+    pub id: u64,
+    pub block: u64,
+    pub times: u32,
+}
+
+/// Walks the invalid IDs of a range without testing the IDs in between: for
+/// each split of the current digit length, the next invalid ID is the smallest
+/// multiple of its repunit that is not behind the cursor.
+pub struct InvalidIds {
+    rule: RepetitionRule,
+    next: u128,
+    end: u128,
+}
+impl Iterator for InvalidIds {
+    type Item = InvalidId;
+
+    fn next(&mut self) -> Option<InvalidId> {
+        let base = self.rule.base as u128;
+        while self.next <= self.end {
+            let len = digit_count(self.next, self.rule.base);
+            let found = self
+                .rule
+                .shapes(len)
+                .filter_map(|(block_len, times)| {
+                    let repunit = repunit(self.rule.base, block_len, times);
+                    let block = self.next.div_ceil(repunit).max(base.pow(block_len - 1));
+                    (block < base.pow(block_len)).then_some((block * repunit, block, times))
+                })
+                // on ties the shortest block wins, and shapes() yields it first
+                .min_by_key(|&(id, _, _)| id);
+
+            match found {
+                Some((id, block, times)) if id <= self.end => {
+                    self.next = id + 1;
+                    return Some(InvalidId {
+                        id: id as u64,
+                        block: block as u64,
+                        times,
+                    });
+                }
+                Some(_) => break,
+                None => self.next = base.pow(len),
+            }
+        }
+        self.next = self.end + 1;
+        None
+    }
+}
+
+fn digit_count(mut n: u128, base: u32) -> u32 {
+    let mut count = 1;
+    while n >= base as u128 {
+        n /= base as u128;
+        count += 1;
+    }
+    count
+}
+
+fn to_base(mut n: u64, base: u32) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(char::from_digit((n % base as u64) as u32, base).unwrap());
+        n /= base as u64;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+/// `times` ones spaced `block_len` digits apart (e.g. 10101 for a block of two
+/// decimal digits written three times): block * repunit repeats the block.
+fn repunit(base: u32, block_len: u32, times: u32) -> u128 {
+    let shift = (base as u128).pow(block_len);
+    (0..times).fold(0, |acc, _| acc * shift + 1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,29 +366,202 @@ mod tests {
 
     #[test]
     fn is_invalid_number_true() {
-        assert_eq!(Day02::is_invalid_number_p1(11), true);
-        assert_eq!(Day02::is_invalid_number_p1(22), true);
-        assert_eq!(Day02::is_invalid_number_p1(55), true);
-        assert_eq!(Day02::is_invalid_number_p1(6464), true);
-        assert_eq!(Day02::is_invalid_number_p1(123123), true);
+        assert!(RepetitionRule::PART1.matches(11));
+        assert!(RepetitionRule::PART1.matches(22));
+        assert!(RepetitionRule::PART1.matches(55));
+        assert!(RepetitionRule::PART1.matches(6464));
+        assert!(RepetitionRule::PART1.matches(123123));
     }
 
     #[test]
     fn is_invalid_number_false() {
-        assert_eq!(Day02::is_invalid_number_p1(7), false);
-        assert_eq!(Day02::is_invalid_number_p1(21), false);
-        assert_eq!(Day02::is_invalid_number_p1(156), false);
-        assert_eq!(Day02::is_invalid_number_p1(784), false);
-        assert_eq!(Day02::is_invalid_number_p1(123143), false);
+        assert!(!RepetitionRule::PART1.matches(7));
+        assert!(!RepetitionRule::PART1.matches(21));
+        assert!(!RepetitionRule::PART1.matches(156));
+        assert!(!RepetitionRule::PART1.matches(784));
+        assert!(!RepetitionRule::PART1.matches(123143));
         //
-        assert_eq!(Day02::is_invalid_number_p1(999), false);
-        assert_eq!(Day02::is_invalid_number_p1(1111111), false);
+        assert!(!RepetitionRule::PART1.matches(999));
+        assert!(!RepetitionRule::PART1.matches(1111111));
     }
 
     #[test]
     fn is_invalid_number_p2_true() {
-        assert_eq!(Day02::is_number_duplicated_n_times(999), true);
-        assert_eq!(Day02::is_number_duplicated_n_times(1111111), true);
+        assert!(RepetitionRule::PART2.matches(999));
+        assert!(RepetitionRule::PART2.matches(1111111));
+    }
+
+    #[test]
+    fn repetition_rule_bounds() {
+        let exactly_three = RepetitionRule::new(Repetitions::Exactly(3), 1, 10).unwrap();
+        assert!(exactly_three.matches(999));
+        assert!(exactly_three.matches(121212));
+        assert!(!exactly_three.matches(123123));
+
+        let at_most_three = RepetitionRule::new(Repetitions::AtMost(3), 1, 10).unwrap();
+        assert!(at_most_three.matches(123123));
+        assert!(at_most_three.matches(111111));
+        assert!(!at_most_three.matches(11111));
+
+        let long_blocks = RepetitionRule::new(Repetitions::AtLeast(2), 2, 10).unwrap();
+        assert!(long_blocks.matches(1212));
+        assert!(!long_blocks.matches(111));
+    }
+
+    #[test]
+    fn repetition_rule_base() {
+        let binary = RepetitionRule::new(Repetitions::Exactly(2), 1, 2).unwrap();
+        assert!(binary.matches(0b1010));
+        assert!(binary.matches(0b11));
+        assert!(!binary.matches(0b1001));
+
+        let hex = RepetitionRule::new(Repetitions::AtLeast(2), 1, 16).unwrap();
+        assert!(hex.matches(0xabab));
+        assert!(hex.matches(0xfff));
+        assert!(!hex.matches(0xabba));
+    }
+
+    #[test]
+    fn repetition_rule_invalid() {
+        assert!(RepetitionRule::new(Repetitions::Exactly(1), 1, 10).is_err());
+        assert!(RepetitionRule::new(Repetitions::AtLeast(2), 0, 10).is_err());
+        assert!(RepetitionRule::new(Repetitions::AtLeast(2), 1, 37).is_err());
+        assert!(RepetitionRule::new(Repetitions::AtLeast(2), 1, 1).is_err());
+    }
+
+    #[test]
+    fn repetition_rule_from_options() {
+        let options = Options::parse(["--exactly", "2"]).unwrap();
+        assert_eq!(
+            RepetitionRule::from_options(&options).unwrap(),
+            RepetitionRule::PART1
+        );
+        let options = Options::parse(Vec::<String>::new()).unwrap();
+        assert_eq!(
+            RepetitionRule::from_options(&options).unwrap(),
+            RepetitionRule::PART2
+        );
+        let options = Options::parse(["--exactly", "2", "--at-most", "4"]).unwrap();
+        assert!(RepetitionRule::from_options(&options).is_err());
+    }
+
+    #[test]
+    fn sums_past_u64() {
+        // two 20-digit invalid IDs whose sum does not fit in a u64
+        let input = "11111111111111111111-11111111121111111112";
+        assert_eq!(Day02.part2(input).unwrap(), "22222222232222222223");
+
+        let mut out = Vec::new();
+        Day02
+            .run("explain", input, &Options::default(), &mut out)
+            .unwrap();
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .ends_with("total: 22222222232222222223\n")
+        );
+    }
+
+    #[test]
+    fn invalid_ids_explained() {
+        let invalid = |id, block, times| InvalidId { id, block, times };
+
+        let ids: Vec<_> = RepetitionRule::PART2.invalid_ids(95..=1012).collect();
+        let mut expected = vec![invalid(99, 9, 2)];
+        expected.extend((1..=9).map(|d| invalid(d * 111, d, 3)));
+        expected.push(invalid(1010, 10, 2));
+        assert_eq!(ids, expected);
+
+        let first = |rule: RepetitionRule, n: u64| rule.invalid_ids(n..=n).next();
+        assert_eq!(
+            first(RepetitionRule::PART2, 111111),
+            Some(invalid(111111, 1, 6))
+        );
+        assert_eq!(
+            first(RepetitionRule::PART1, 111111),
+            Some(invalid(111111, 111, 2))
+        );
+    }
+
+    #[test]
+    fn invalid_ids_match_brute_force() {
+        let rules = [
+            RepetitionRule::PART1,
+            RepetitionRule::PART2,
+            RepetitionRule::new(Repetitions::AtMost(3), 2, 10).unwrap(),
+            RepetitionRule::new(Repetitions::AtLeast(2), 1, 3).unwrap(),
+            RepetitionRule::new(Repetitions::Exactly(4), 1, 2).unwrap(),
+        ];
+        for rule in rules {
+            let fast: Vec<_> = rule.invalid_ids(0..=200_000).map(|i| i.id).collect();
+            let slow: Vec<_> = (0..=200_000).filter(|&n| rule.matches(n)).collect();
+            assert_eq!(fast, slow, "{:?}", rule);
+        }
+        assert_eq!(
+            RepetitionRule::PART2
+                .invalid_ids(u64::MAX - 5..=u64::MAX)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn explain_test() {
+        let mut out = Vec::new();
+        let ranges = parse_input("11-22,95-115,1698522-1698528").unwrap();
+        Day02
+            .explain(&ranges, RepetitionRule::PART1, &mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "11-22 has 2 invalid IDs, 11 (1 x 2) and 22 (2 x 2).
+95-115 has one invalid ID, 99 (9 x 2).
+1698522-1698528 contains no invalid IDs.
+total: 132
+"
+        );
+    }
+
+    #[test]
+    fn parse_input_separators() {
+        assert_eq!(
+            parse_input("11-22, 95-115\n998-1012\t\r\n,,4-4,\n").unwrap(),
+            vec![11..=22, 95..=115, 998..=1012, 4..=4]
+        );
+    }
+
+    #[test]
+    fn parse_input_errors() {
+        let error = |input| parse_input(input).unwrap_err();
+        assert_eq!(
+            error("1-2,11-"),
+            ParseError {
+                line: 1,
+                column: 5,
+                text: "11-".to_string(),
+                reason: "missing end"
+            }
+        );
+        assert_eq!(error("a-b").reason, "not a number");
+        assert_eq!(error("-5").reason, "missing start");
+        assert_eq!(error("11-22-33").reason, "too many '-'");
+        assert_eq!(error("22-11").reason, "start is after end");
+        assert_eq!(error("1122").reason, "missing '-'");
+        assert_eq!(
+            error("1-2,\n 95-\n115").to_string(),
+            "'95-' at line 2, column 2: missing end"
+        );
+    }
+
+    #[test]
+    fn parse_input_lenient_test() {
+        let (ranges, skipped) = parse_input_lenient("11-22,a-b,95-115 11-");
+        assert_eq!(ranges, vec![11..=22, 95..=115]);
+        let skipped: Vec<_> = skipped
+            .iter()
+            .map(|e| (e.column, e.text.as_str()))
+            .collect();
+        assert_eq!(skipped, vec![(7, "a-b"), (18, "11-")]);
     }
 
     #[test]
@@ -0,0 +1,502 @@
+use crate::big::BigUint;
+use crate::{AocResult, Day, Options};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+
+pub struct Day03;
+impl Day for Day03 {
+    fn name(&self) -> &'static str {
+        "day03"
+    }
+
+    fn part1(&self, input: &str) -> AocResult<String> {
+        let banks = Self::usable_banks(input, 2, false)?;
+        Ok(Self::total_joltage(&banks, 2)?.to_string())
+    }
+
+    fn part2(&self, input: &str) -> AocResult<String> {
+        let banks = Self::usable_banks(input, 12, false)?;
+        Ok(Self::total_joltage(&banks, 12)?.to_string())
+    }
+
+    fn run(
+        &self,
+        command: &str,
+        input: &str,
+        options: &Options,
+        out: &mut dyn Write,
+    ) -> AocResult<()> {
+        let digits = || -> AocResult<usize> {
+            options
+                .get("digits")?
+                .ok_or_else(|| format!("{} needs --digits k", command).into())
+        };
+        match command {
+            "joltage" => {
+                options.allow(&["digits", "lenient"])?;
+                let digits = digits()?;
+                let banks = Self::usable_banks(input, digits, options.flag("lenient"))?;
+                writeln!(out, "{}", Self::total_joltage(&banks, digits)?)?;
+                Ok(())
+            }
+            "report" => {
+                options.allow(&["digits", "lenient"])?;
+                let digits = digits()?;
+                let banks = Self::usable_banks(input, digits, options.flag("lenient"))?;
+                Self::report(&banks, digits, out)
+            }
+            "curve" => {
+                options.allow(&[])?;
+                Self::curve_csv(&Self::convert_batteries(input)?, out)
+            }
+            _ => Err(format!(
+                "day03 has no command '{}' (available: joltage, report, curve)",
+                command
+            )
+            .into()),
+        }
+    }
+}
+
+impl Day03 {
+    /// One bank per non-blank line; trailing whitespace (such as the `\r` of a
+    /// CRLF file) is ignored.
+    fn convert_batteries(input: &str) -> Result<Vec<Bank>, BankError> {
+        input
+            .lines()
+            .enumerate()
+            .map(|(row, line)| (row + 1, line.trim_end()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(line, text)| {
+                let batteries = text
+                    .chars()
+                    .enumerate()
+                    .map(|(col, c)| {
+                        c.to_digit(10)
+                            .map(|d| d as u8)
+                            .ok_or(BankError::InvalidBattery {
+                                line,
+                                column: col + 1,
+                                found: c,
+                            })
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(Bank { line, batteries })
+            })
+            .collect()
+    }
+
+    /// The banks with at least `digits` batteries. Shorter banks are an error,
+    /// unless `lenient`, in which case they are skipped with a warning.
+    fn usable_banks(input: &str, digits: usize, lenient: bool) -> AocResult<Vec<Bank>> {
+        let (usable, short): (Vec<_>, Vec<_>) = Self::convert_batteries(input)?
+            .into_iter()
+            .partition(|bank| bank.batteries.len() >= digits);
+
+        match short.first() {
+            Some(bank) if !lenient => Err(bank.too_short(digits).into()),
+            _ => {
+                for bank in &short {
+                    eprintln!("skipped {}", bank.too_short(digits));
+                }
+                Ok(usable)
+            }
+        }
+    }
+
+    fn select(bank: &Bank, digits: usize) -> Result<Selection, BankError> {
+        max_joltage(&bank.batteries, digits).ok_or_else(|| bank.too_short(digits))
+    }
+
+    fn total_joltage(banks: &[Bank], digits: usize) -> AocResult<BigUint> {
+        banks
+            .iter()
+            .map(|bank| Ok(Self::select(bank, digits)?.joltage))
+            .sum()
+    }
+
+    /// Prints every bank with a `^` under each battery turned on, followed by
+    /// the bank joltage, then the total.
+    fn report(banks: &[Bank], digits: usize, out: &mut dyn Write) -> AocResult<()> {
+        let mut total = BigUint::zero();
+        for bank in banks {
+            let selection = Self::select(bank, digits)?;
+
+            let mut marks = vec![' '; bank.batteries.len()];
+            for &i in &selection.indices {
+                marks[i] = '^';
+            }
+            let batteries: String = bank.batteries.iter().map(|d| (b'0' + d) as char).collect();
+            let marks: String = marks.into_iter().collect();
+
+            writeln!(out, "{}", batteries)?;
+            writeln!(out, "{}  {}", marks, selection.joltage)?;
+            total += &selection.joltage;
+        }
+        writeln!(out, "total: {}", total)?;
+        Ok(())
+    }
+
+    /// Writes the best joltage of every bank for every `k` as CSV rows of
+    /// `bank,k,joltage`, banks identified by their line number.
+    fn curve_csv(banks: &[Bank], out: &mut dyn Write) -> AocResult<()> {
+        writeln!(out, "bank,k,joltage")?;
+        for bank in banks {
+            for (k, joltage) in joltage_curve(&bank.batteries).iter().enumerate() {
+                writeln!(out, "{},{},{}", bank.line, k + 1, joltage)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Bank {
+    line: usize,
+    batteries: Vec<u8>,
+}
+impl Bank {
+    fn too_short(&self, digits: usize) -> BankError {
+        BankError::TooShort {
+            line: self.line,
+            len: self.batteries.len(),
+            digits,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum BankError {
+    InvalidBattery {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    TooShort {
+        line: usize,
+        len: usize,
+        digits: usize,
+    },
+}
+
+impl Display for BankError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BankError::InvalidBattery {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "invalid battery {:?} at line {}, column {}",
+                found, line, column
+            ),
+            BankError::TooShort { line, len, digits } => write!(
+                f,
+                "bank at line {} has {} batteries, fewer than the {} to turn on",
+                line, len, digits
+            ),
+        }
+    }
+}
+impl Error for BankError {}
+
+/// The batteries turned on in a bank, by position, and the joltage they make.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub indices: Vec<usize>,
+    pub joltage: BigUint,
+}
+
+/// The largest joltage made by turning on `k` batteries of the bank, or
+/// `None` if it has fewer than `k`.
+///
+/// Keeps the chosen batteries on a stack: a new digit pops every smaller digit
+/// before it as long as enough batteries are left to still reach `k`.
+pub fn max_joltage(bank: &[u8], k: usize) -> Option<Selection> {
+    let mut droppable = bank.len().checked_sub(k)?;
+    let mut stack: Vec<usize> = Vec::with_capacity(bank.len());
+
+    for (i, &digit) in bank.iter().enumerate() {
+        while droppable > 0 && stack.last().is_some_and(|&top| bank[top] < digit) {
+            stack.pop();
+            droppable -= 1;
+        }
+        stack.push(i);
+    }
+    stack.truncate(k);
+
+    let digits: Vec<u8> = stack.iter().map(|&i| bank[i]).collect();
+    Some(Selection {
+        joltage: BigUint::from_digits(&digits),
+        indices: stack,
+    })
+}
+
+/// The best joltage of the bank for every `k` from 1 to its length, at index
+/// `k - 1`.
+///
+/// Dropping one battery at a time greedily stays optimal for every size, so the
+/// order in which batteries get dropped gives every selection at once.
+pub fn joltage_curve(bank: &[u8]) -> Vec<BigUint> {
+    let removed_at = removal_order(bank);
+    let n = bank.len();
+
+    (1..=n)
+        .map(|k| {
+            let digits: Vec<u8> = (0..n)
+                .filter(|&i| removed_at[i] >= n - k)
+                .map(|i| bank[i])
+                .collect();
+            BigUint::from_digits(&digits)
+        })
+        .collect()
+}
+
+/// For each battery, how many others are dropped before it when shrinking the
+/// bank one battery at a time, always dropping the first battery followed by a
+/// bigger one (or the last battery when there is none).
+fn removal_order(bank: &[u8]) -> Vec<usize> {
+    let n = bank.len();
+    let mut prev: Vec<Option<usize>> = (0..n).map(|i| i.checked_sub(1)).collect();
+    let mut next: Vec<Option<usize>> = (0..n).map(|i| Some(i + 1).filter(|&j| j < n)).collect();
+    let mut removed_at = vec![n.saturating_sub(1); n];
+    let mut cur = 0;
+
+    for step in 0..n.saturating_sub(1) {
+        while let Some(j) = next[cur].filter(|&j| bank[cur] >= bank[j]) {
+            cur = j;
+        }
+        removed_at[cur] = step;
+
+        if let Some(p) = prev[cur] {
+            next[p] = next[cur];
+        }
+        if let Some(j) = next[cur] {
+            prev[j] = prev[cur];
+        }
+        cur = prev[cur].or(next[cur]).unwrap();
+    }
+
+    removed_at
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static INPUT: &str = "987654321111111
+811111111111119
+234234234234278
+818181911112111";
+
+    fn joltage(bank: &[u8], k: usize) -> Option<u128> {
+        max_joltage(bank, k).map(|s| s.joltage.to_u128().unwrap())
+    }
+
+    #[test]
+    fn max_joltage_edge_cases() {
+        assert_eq!(joltage(&[1, 2, 3], 3), Some(123));
+        assert_eq!(joltage(&[1, 2, 3], 4), None);
+        assert_eq!(
+            max_joltage(&[1, 2, 3], 0),
+            Some(Selection {
+                indices: vec![],
+                joltage: BigUint::zero()
+            })
+        );
+        assert_eq!(joltage(&[5, 5, 9, 5, 5], 3), Some(955));
+    }
+
+    #[test]
+    /**
+    In 234234234234278, the largest joltage can be found by turning on everything except a 2 battery,
+    a 3 battery, and another 2 battery near the start to produce 434234234278.
+     */
+    fn max_joltage_indices_test() {
+        let selection = max_joltage(&[2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8], 12).unwrap();
+        assert_eq!(
+            selection.indices,
+            vec![2, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14]
+        );
+        assert_eq!(selection.joltage.to_u128(), Some(434234234278));
+    }
+
+    #[test]
+    fn report_test() {
+        let mut out = Vec::new();
+        let banks = Day03::convert_batteries(INPUT).unwrap();
+        Day03::report(&banks, 2, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "987654321111111
+^^               98
+811111111111119
+^             ^  89
+234234234234278
+             ^^  78
+818181911112111
+      ^    ^     92
+total: 357
+"
+        );
+    }
+
+    #[test]
+    fn joltage_curve_matches_max_joltage() {
+        for bank in INPUT
+            .lines()
+            .chain(["5", "11111", "12345", "54321", "3141592653589793"])
+        {
+            let bank: Vec<u8> = bank.bytes().map(|b| b - b'0').collect();
+            let expected: Vec<_> = (1..=bank.len())
+                .map(|k| max_joltage(&bank, k).unwrap().joltage)
+                .collect();
+            assert_eq!(joltage_curve(&bank), expected);
+        }
+        assert!(joltage_curve(&[]).is_empty());
+    }
+
+    #[test]
+    fn curve_csv_test() {
+        let mut out = Vec::new();
+        let banks = Day03::convert_batteries("132\n9").unwrap();
+        Day03::curve_csv(&banks, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "bank,k,joltage\n1,1,3\n1,2,32\n1,3,132\n2,1,9\n"
+        );
+    }
+
+    #[test]
+    fn convert_batteries_errors() {
+        let banks = Day03::convert_batteries("123\r\n\n45 \r\n").unwrap();
+        assert_eq!(
+            banks,
+            vec![
+                Bank {
+                    line: 1,
+                    batteries: vec![1, 2, 3]
+                },
+                Bank {
+                    line: 3,
+                    batteries: vec![4, 5]
+                }
+            ]
+        );
+        assert_eq!(
+            Day03::convert_batteries("123\n45x6"),
+            Err(BankError::InvalidBattery {
+                line: 2,
+                column: 3,
+                found: 'x'
+            })
+        );
+        assert_eq!(
+            Day03::convert_batteries("12\r3").unwrap_err().to_string(),
+            "invalid battery '\\r' at line 1, column 3"
+        );
+    }
+
+    #[test]
+    fn usable_banks_test() {
+        let error = Day03::usable_banks("987\n1\n55", 2, false).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "bank at line 2 has 1 batteries, fewer than the 2 to turn on"
+        );
+        assert!(Day03.part1("987\n1").is_err());
+
+        let banks = Day03::usable_banks("987\n1\n55", 2, true).unwrap();
+        assert_eq!(Day03::total_joltage(&banks, 2).unwrap().to_string(), "153");
+    }
+
+    #[test]
+    fn max_joltage_beyond_u128() {
+        let bank: Vec<u8> = (0..60).map(|i| (i * 7 % 10) as u8).collect();
+        assert_eq!(
+            max_joltage(&bank, 45).unwrap().joltage.to_string(),
+            "989630741852963074185296307418529630741852963"
+        );
+    }
+
+    #[test]
+    fn max_joltage_12_test() {
+        assert_eq!(
+            joltage(&[9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1], 12),
+            Some(987654321111)
+        );
+        assert_eq!(
+            joltage(&[8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9], 12),
+            Some(811111111119)
+        );
+        assert_eq!(
+            joltage(&[2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8], 12),
+            Some(434234234278)
+        );
+        assert_eq!(
+            joltage(&[8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1], 12),
+            Some(888911112111)
+        );
+    }
+
+    #[test]
+    fn max_joltage_2_test() {
+        assert_eq!(
+            joltage(&[9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1], 2),
+            Some(98)
+        );
+        assert_eq!(
+            joltage(&[8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9], 2),
+            Some(89)
+        );
+        assert_eq!(
+            joltage(&[2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8], 2),
+            Some(78)
+        );
+        assert_eq!(
+            joltage(&[8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1], 2),
+            Some(92)
+        );
+    }
+
+    #[test]
+    fn max_joltage_2_long_bank_test() {
+        assert_eq!(
+            joltage(
+                &[
+                    3, 4, 3, 4, 8, 4, 5, 6, 3, 4, 4, 5, 4, 3, 6, 4, 5, 4, 6, 3, 3, 4, 3, 3, 5, 3,
+                    3, 3, 4, 4, 8, 4, 4, 3, 3, 5, 4, 3, 2, 4, 5, 3, 3, 5, 4, 5, 4, 4, 3, 2, 3, 5,
+                    4, 1, 4, 3, 3, 4, 4, 7, 7, 4, 2, 4, 4, 4, 2, 4, 4, 4, 3, 4, 6, 8, 4, 4, 3, 4,
+                    4, 2, 4, 4, 4, 4, 4, 4, 3, 4, 4, 4, 5, 3, 3, 3, 3, 4, 4, 3, 1, 4
+                ],
+                2
+            ),
+            Some(88)
+        );
+    }
+
+    #[test]
+    /**
+    In 987654321111111, you can make the largest joltage possible, 98, by turning on the first two batteries.
+    In 811111111111119, you can make the largest joltage possible by turning on the batteries labeled 8 and 9, producing 89 jolts.
+    In 234234234234278, you can make 78 by turning on the last two batteries (marked 7 and 8).
+    In 818181911112111, the largest joltage you can produce is 92.
+    output joltage is 98 + 89 + 78 + 92 = 357.
+     */
+    fn day03_part1_test() {
+        assert_eq!(Day03.part1(INPUT).unwrap(), "357");
+    }
+    #[test]
+    /**
+    In 987654321111111, the largest joltage can be found by turning on everything except some 1s at the end to produce 987654321111.
+    In the digit sequence 811111111111119, the largest joltage can be found by turning on everything except some 1s, producing 811111111119.
+    In 234234234234278, the largest joltage can be found by turning on everything except a 2 battery, a 3 battery, and another 2 battery near the start to produce 434234234278.
+    In 818181911112111, the joltage 888911112111 is produced by turning on everything except some 1s near the front.
+    joltage is now much larger: 987654321111 + 811111111119 + 434234234278 + 888911112111 = 3121910778619
+     */
+    fn day03_part2_test() {
+        assert_eq!(Day03.part2(INPUT).unwrap(), "3121910778619");
+    }
+}
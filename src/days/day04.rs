@@ -1,4 +1,8 @@
-use crate::{AocResult, Day};
+use crate::grid::{BitGrid, Boundary, Grid, MOORE, Neighbour};
+use crate::{AocResult, Day, Options};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::str::FromStr;
 
 pub struct Day04;
 impl Day for Day04 {
@@ -7,6 +11,564 @@ impl Day for Day04 {
     }
 
     fn part1(&self, input: &str) -> AocResult<String> {
+        let grid = BitGrid::parse(input, |c| c == '@');
+        Ok(count_accessible(&grid).to_string())
+    }
+
+    fn part2(&self, input: &str) -> AocResult<String> {
+        let grid = BitGrid::parse(input, |c| c == '@');
+        Ok(remove_all(&grid, &Rules::puzzle()).total().to_string())
+    }
+
+    fn run(
+        &self,
+        command: &str,
+        input: &str,
+        options: &Options,
+        out: &mut dyn Write,
+    ) -> AocResult<()> {
+        if !matches!(command, "accessible" | "remove" | "rounds" | "depth") {
+            return Err(format!(
+                "day04 has no command '{}' (available: accessible, remove, rounds, depth)",
+                command
+            )
+            .into());
+        }
+        options.allow(&Rules::OPTIONS)?;
+        let rules = Rules::from_options(options)?;
+        let grid = BitGrid::parse(input, |c| c == '@');
+        match command {
+            "accessible" => writeln!(out, "{}", count_removable(&grid, &rules))?,
+            "remove" => writeln!(out, "{}", remove_all(&grid, &rules).total())?,
+            "rounds" => remove_all_with_depth(&grid, &rules).write_rounds(&grid, out)?,
+            _ => remove_all_with_depth(&grid, &rules).write_depth(&grid, out)?,
+        }
+        Ok(())
+    }
+}
+
+/// Which cells around a roll count as its neighbours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// Every cell within `r` steps, diagonals included.
+    Moore(usize),
+    /// Every cell within `r` orthogonal steps.
+    VonNeumann(usize),
+    /// An explicit list of `(row, col)` offsets.
+    Custom(Vec<(isize, isize)>),
+}
+impl Neighbourhood {
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        let square = |r: usize| {
+            let r = r as isize;
+            (-r..=r).flat_map(move |dr| (-r..=r).map(move |dc| (dr, dc)))
+        };
+        match self {
+            Neighbourhood::Moore(r) => square(*r).filter(|&d| d != (0, 0)).collect(),
+            Neighbourhood::VonNeumann(r) => square(*r)
+                .filter(|&(dr, dc)| (dr, dc) != (0, 0))
+                .filter(|&(dr, dc)| dr.unsigned_abs() + dc.unsigned_abs() <= *r)
+                .collect(),
+            Neighbourhood::Custom(offsets) => offsets.clone(),
+        }
+    }
+
+    /// How many offsets `offsets` would list, counted without listing them,
+    /// or `None` when there are more than a `usize` holds.
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            Neighbourhood::Moore(r) => {
+                let side = r.checked_mul(2)?.checked_add(1)?;
+                Some(side.checked_mul(side)? - 1)
+            }
+            Neighbourhood::VonNeumann(r) => r.checked_mul(2)?.checked_mul(r.checked_add(1)?),
+            Neighbourhood::Custom(offsets) => Some(offsets.len()),
+        }
+    }
+}
+
+/// How the number of neighbouring rolls is compared to the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+impl Comparison {
+    fn holds(&self, count: u16, threshold: u16) -> bool {
+        match self {
+            Comparison::Less => count < threshold,
+            Comparison::LessOrEqual => count <= threshold,
+            Comparison::Greater => count > threshold,
+            Comparison::GreaterOrEqual => count >= threshold,
+            Comparison::Equal => count == threshold,
+            Comparison::NotEqual => count != threshold,
+        }
+    }
+}
+impl FromStr for Comparison {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "<" | "lt" => Ok(Comparison::Less),
+            "<=" | "le" => Ok(Comparison::LessOrEqual),
+            ">" | "gt" => Ok(Comparison::Greater),
+            ">=" | "ge" => Ok(Comparison::GreaterOrEqual),
+            "==" | "eq" => Ok(Comparison::Equal),
+            "!=" | "ne" => Ok(Comparison::NotEqual),
+            _ => Err(format!("unknown comparison: {}", s)),
+        }
+    }
+}
+
+/// Whether a round decides every removal before applying any, or removals are
+/// applied one roll at a time and seen by the rolls checked after them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Simultaneous,
+    Sequential,
+}
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "simultaneous" => Ok(Mode::Simultaneous),
+            "sequential" => Ok(Mode::Sequential),
+            _ => Err(format!("unknown mode: {}", s)),
+        }
+    }
+}
+
+/// A roll can be removed when its number of neighbouring rolls compares to
+/// `threshold` as `comparison` says. Neighbours past the edge of the grid are
+/// found through `boundary`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    offsets: Vec<(isize, isize)>,
+    comparison: Comparison,
+    threshold: u16,
+    mode: Mode,
+    boundary: Boundary,
+}
+impl Rules {
+    pub fn new(
+        neighbourhood: Neighbourhood,
+        comparison: Comparison,
+        threshold: u16,
+        mode: Mode,
+    ) -> AocResult<Self> {
+        // counts are u16s; checked before a huge radius is listed out
+        match neighbourhood.size() {
+            Some(len) if len <= u16::MAX as usize => {}
+            Some(len) => return Err(format!("neighbourhood too large: {} cells", len).into()),
+            None => return Err("neighbourhood too large: more cells than usize holds".into()),
+        }
+        Ok(Rules {
+            offsets: neighbourhood.offsets(),
+            comparison,
+            threshold,
+            mode,
+            boundary: Boundary::Empty,
+        })
+    }
+
+    pub fn with_boundary(self, boundary: Boundary) -> Self {
+        Rules { boundary, ..self }
+    }
+
+    /// Fewer than four of the eight surrounding rolls, one round at a time.
+    pub fn puzzle() -> Self {
+        Rules {
+            offsets: MOORE.to_vec(),
+            comparison: Comparison::Less,
+            threshold: 4,
+            mode: Mode::Simultaneous,
+            boundary: Boundary::Empty,
+        }
+    }
+
+    /// The options `from_options` reads.
+    pub const OPTIONS: [&'static str; 7] = [
+        "neighbourhood",
+        "radius",
+        "offsets",
+        "compare",
+        "threshold",
+        "mode",
+        "boundary",
+    ];
+
+    /// Reads `--neighbourhood moore|von-neumann` with `--radius r`, or
+    /// `--offsets "dr,dc;dr,dc..."`, then `--compare`, `--threshold`, `--mode`
+    /// and `--boundary`. Anything not given keeps the puzzle's value.
+    pub fn from_options(options: &Options) -> AocResult<Self> {
+        if options.flag("offsets") && (options.flag("neighbourhood") || options.flag("radius")) {
+            return Err("--offsets cannot be combined with --neighbourhood or --radius".into());
+        }
+        let radius = options.get("radius")?.unwrap_or(1);
+        let neighbourhood = match (options.value("offsets"), options.value("neighbourhood")) {
+            (Some(list), _) => Neighbourhood::Custom(parse_offsets(list)?),
+            (None, None | Some("moore")) => Neighbourhood::Moore(radius),
+            (None, Some("von-neumann")) => Neighbourhood::VonNeumann(radius),
+            (None, Some(other)) => return Err(format!("unknown neighbourhood: {}", other).into()),
+        };
+
+        let rules = Rules::new(
+            neighbourhood,
+            options.get("compare")?.unwrap_or(Comparison::Less),
+            options.get("threshold")?.unwrap_or(4),
+            options.get("mode")?.unwrap_or(Mode::Simultaneous),
+        )?;
+        Ok(rules.with_boundary(options.get("boundary")?.unwrap_or(Boundary::Empty)))
+    }
+}
+
+fn parse_offsets(list: &str) -> AocResult<Vec<(isize, isize)>> {
+    list.split(|c: char| c == ';' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|pair| {
+            let (dr, dc) = pair
+                .split_once(',')
+                .ok_or_else(|| format!("offset is not 'row,col': {}", pair))?;
+            Ok((dr.trim().parse()?, dc.trim().parse()?))
+        })
+        .collect()
+}
+
+/// The rolls present, one bit per cell, and for every roll how many of the
+/// cells it counts as neighbours hold a roll. The counts of empty cells are
+/// never read, and stay 0.
+struct Removal<'a> {
+    grid: &'a BitGrid,
+    rules: &'a Rules,
+    present: BitGrid,
+    counts: Vec<u16>,
+}
+impl<'a> Removal<'a> {
+    /// Only the rolls are visited, found a word at a time.
+    fn new(grid: &'a BitGrid, rules: &'a Rules) -> Self {
+        let mut counts = vec![0; grid.width() * grid.height()];
+        for i in grid.ones() {
+            counts[i] = grid
+                .neighbours_with(i, &rules.offsets, rules.boundary)
+                .filter(|&n| match n {
+                    Neighbour::Cell(j) => grid.at(j),
+                    Neighbour::Outside => true,
+                })
+                .count() as u16;
+        }
+        Removal {
+            grid,
+            rules,
+            present: grid.clone(),
+            counts,
+        }
+    }
+
+    fn removable(&self, i: usize) -> bool {
+        self.present.at(i)
+            && self
+                .rules
+                .comparison
+                .holds(self.counts[i], self.rules.threshold)
+    }
+
+    /// Updates the counts after the roll at `i` has been taken away, calling
+    /// `touched` with every roll whose count went down.
+    fn release(&mut self, i: usize, mut touched: impl FnMut(usize)) {
+        let (grid, rules) = (self.grid, self.rules);
+        for j in grid.counted_by(i, &rules.offsets, rules.boundary) {
+            if self.present.at(j) {
+                self.counts[j] -= 1;
+                touched(j);
+            }
+        }
+    }
+
+    /// Each round removes every roll removable at its start. Only the rolls
+    /// next to a removal can change, so only those are checked again.
+    fn simultaneous(&mut self, mut history: History) -> History {
+        let mut removable: Vec<usize> =
+            self.present.ones().filter(|&i| self.removable(i)).collect();
+        let mut candidate = BitGrid::new(self.grid.width(), self.grid.height());
+
+        for round in 1.. {
+            if removable.is_empty() {
+                break;
+            }
+            for &i in &removable {
+                self.present.set_at(i, false);
+                history.record(i, round);
+            }
+
+            let mut candidates = Vec::new();
+            for &i in &removable {
+                self.release(i, |j| {
+                    if !candidate.at(j) {
+                        candidate.set_at(j, true);
+                        candidates.push(j);
+                    }
+                });
+            }
+
+            for &j in &candidates {
+                candidate.set_at(j, false);
+            }
+            removable = candidates
+                .into_iter()
+                .filter(|&i| self.removable(i))
+                .collect();
+        }
+        history
+    }
+
+    /// Checks rolls from a queue, in reading order first. A removal is seen
+    /// right away, and queues again the rolls whose count it changed. Those
+    /// belong to the round after the removal that queued them.
+    fn sequential(&mut self, mut history: History) -> History {
+        let mut queue: VecDeque<(usize, u32)> = self.present.ones().map(|i| (i, 1)).collect();
+        let mut queued = self.present.clone();
+
+        while let Some((i, round)) = queue.pop_front() {
+            queued.set_at(i, false);
+            if self.removable(i) {
+                self.present.set_at(i, false);
+                history.record(i, round);
+                self.release(i, |j| {
+                    if !queued.at(j) {
+                        queued.set_at(j, true);
+                        queue.push_back((j, round + 1));
+                    }
+                });
+            }
+        }
+        history
+    }
+}
+
+/// What a removal run did: how many rolls each round removed and, if asked
+/// for, the round (counted from 1) in which each cell lost its roll, 0 if it
+/// never did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    pub rounds: Vec<usize>,
+    pub depth: Option<Grid<u32>>,
+}
+impl History {
+    fn new() -> Self {
+        History {
+            rounds: Vec::new(),
+            depth: None,
+        }
+    }
+
+    fn with_depth(grid: &BitGrid) -> Self {
+        History {
+            rounds: Vec::new(),
+            depth: Some(Grid::filled(grid.width(), grid.height(), 0)),
+        }
+    }
+
+    fn record(&mut self, i: usize, round: u32) {
+        if let Some(depth) = &mut self.depth {
+            depth[i] = round;
+        }
+        if self.rounds.len() < round as usize {
+            self.rounds.resize(round as usize, 0);
+        }
+        self.rounds[round as usize - 1] += 1;
+    }
+
+    pub fn total(&self) -> usize {
+        self.rounds.iter().sum()
+    }
+
+    /// Every round as in the puzzle: the grid with `x` on the rolls removed
+    /// in that round and `.` on those already gone.
+    fn write_rounds(&self, grid: &BitGrid, out: &mut dyn Write) -> AocResult<()> {
+        writeln!(out, "Initial state:")?;
+        self.write_grid(grid, out, |_| '@')?;
+        for (round, &removed) in (1..).zip(&self.rounds) {
+            let plural = if removed == 1 { "roll" } else { "rolls" };
+            writeln!(out, "\nRemove {} {} of paper:", removed, plural)?;
+            self.write_grid(grid, out, |depth| match depth {
+                0 => '@',
+                d if d < round => '.',
+                d if d == round => 'x',
+                _ => '@',
+            })?;
+        }
+        writeln!(
+            out,
+            "\ntotal of {} rolls of paper can be removed",
+            self.total()
+        )?;
+        Ok(())
+    }
+
+    /// The grid with the round each roll was removed in (`1`-`9`, then `a`-`z`,
+    /// `#` past 35), and `@` for the rolls that stay.
+    fn write_depth(&self, grid: &BitGrid, out: &mut dyn Write) -> AocResult<()> {
+        self.write_grid(grid, out, |depth| match depth {
+            0 => '@',
+            d => char::from_digit(d, 36).unwrap_or('#'),
+        })
+    }
+
+    fn write_grid(
+        &self,
+        grid: &BitGrid,
+        out: &mut dyn Write,
+        roll: impl Fn(u32) -> char,
+    ) -> AocResult<()> {
+        let depth = self
+            .depth
+            .as_ref()
+            .ok_or("removal depth was not recorded")?;
+        for row in 0..grid.height() {
+            let line: String = (0..grid.width())
+                .map(|col| {
+                    let i = row * grid.width() + col;
+                    if grid.at(i) { roll(depth[i]) } else { '.' }
+                })
+                .collect();
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// How many rolls could be removed from the grid as it is.
+fn count_removable(grid: &BitGrid, rules: &Rules) -> usize {
+    let removal = Removal::new(grid, rules);
+    grid.ones().filter(|&i| removal.removable(i)).count()
+}
+
+/// Removes rolls until none can go and returns what went when.
+///
+/// Keeps the number of neighbouring rolls of every cell, and after each
+/// removal only updates and re-checks the cells around it, so the work is
+/// proportional to the grid size plus the neighbours of the removed rolls.
+fn remove_all(grid: &BitGrid, rules: &Rules) -> History {
+    run_removal(grid, rules, History::new())
+}
+
+/// As `remove_all`, also recording the round each roll went in, at 4 bytes
+/// per cell.
+fn remove_all_with_depth(grid: &BitGrid, rules: &Rules) -> History {
+    run_removal(grid, rules, History::with_depth(grid))
+}
+
+fn run_removal(grid: &BitGrid, rules: &Rules, history: History) -> History {
+    let mut removal = Removal::new(grid, rules);
+    match rules.mode {
+        Mode::Simultaneous => removal.simultaneous(history),
+        Mode::Sequential => removal.sequential(history),
+    }
+}
+
+/// Counts the rolls with fewer than four rolls around them, 64 cells at a time.
+///
+/// For every word, the eight neighbour masks (the rows above, alongside and
+/// below, shifted one column each way) are added into bit-sliced counters;
+/// `four` latches as soon as a cell has seen four neighbours.
+fn count_accessible(grid: &BitGrid) -> usize {
+    let empty = vec![0; grid.words_per_row()];
+    let row_or_empty = |r: Option<usize>| match r {
+        Some(r) if r < grid.height() => grid.row(r),
+        _ => &empty,
+    };
+
+    (0..grid.height())
+        .map(|r| {
+            let up = row_or_empty(r.checked_sub(1));
+            let mid = grid.row(r);
+            let down = row_or_empty(Some(r + 1));
+
+            (0..grid.words_per_row())
+                .map(|w| {
+                    let (mut ones, mut twos, mut four) = (0u64, 0u64, 0u64);
+                    let mut add = |mask: u64| {
+                        let carry = ones & mask;
+                        ones ^= mask;
+                        four |= twos & carry;
+                        twos ^= carry;
+                    };
+                    for row in [up, down] {
+                        add(row[w]);
+                        add(from_left(row, w));
+                        add(from_right(row, w));
+                    }
+                    add(from_left(mid, w));
+                    add(from_right(mid, w));
+
+                    (mid[w] & !four).count_ones() as usize
+                })
+                .sum::<usize>()
+        })
+        .sum()
+}
+
+/// Word `w` of the row shifted one column, so that each cell holds its left
+/// neighbour.
+fn from_left(row: &[u64], w: usize) -> u64 {
+    let carry = if w > 0 { row[w - 1] >> 63 } else { 0 };
+    (row[w] << 1) | carry
+}
+
+/// Word `w` of the row shifted one column, so that each cell holds its right
+/// neighbour.
+fn from_right(row: &[u64], w: usize) -> u64 {
+    let carry = row.get(w + 1).map_or(0, |next| next << 63);
+    (row[w] >> 1) | carry
+}
+
+/// The original `Vec<Vec<bool>>` implementation, kept to check the faster
+/// solvers against.
+#[cfg(test)]
+mod reference {
+    pub(super) fn convert_input(input: &str) -> Vec<Vec<bool>> {
+        input
+            .lines()
+            .map(|line| line.chars().map(|c| c == '@').collect::<Vec<_>>())
+            .collect()
+    }
+    const DELTAS: [(isize, isize); 8] = [
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, -1),
+        (0, 1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+    ];
+
+    pub(super) fn count_adjacent(row: u16, col: u16, grid: &[Vec<bool>]) -> u16 {
+        DELTAS
+            .iter()
+            .filter_map(|(deviance_row, deviance_column)| {
+                let adjacent_row = row as isize + deviance_row;
+                let adjacent_column = col as isize + deviance_column;
+
+                if adjacent_row < 0 || adjacent_column < 0 {
+                    return None;
+                }
+
+                grid.get(adjacent_row as usize)
+                    .and_then(|row: &Vec<bool>| row.get(adjacent_column as usize))
+                    .copied()
+            })
+            .filter(|&v| v)
+            .count() as u16
+    }
+
+    pub(super) fn count_accessible(input: &str) -> usize {
         let lines: Vec<Vec<bool>> = convert_input(input);
 
         let count: i32 = lines
@@ -27,94 +589,36 @@ impl Day for Day04 {
             })
             .sum();
 
-        Ok(count.to_string())
+        count as usize
     }
 
-    fn part2(&self, input: &str) -> AocResult<String> {
-        let mut lines: Vec<Vec<bool>> = convert_input(input);
-        let mut should_continue = true;
-        let mut count: i32 = 0;
-
-        while should_continue {
-            let count_before = count_total_true(&lines);
-
-            lines = lines
+    /// Recomputes every cell each round until nothing changes.
+    pub(super) fn remove_by_rounds(mut lines: Vec<Vec<bool>>) -> usize {
+        let total = |grid: &[Vec<bool>]| grid.iter().flatten().filter(|&&v| v).count();
+        let before = total(&lines);
+        loop {
+            let next: Vec<Vec<bool>> = lines
                 .iter()
                 .enumerate()
                 .map(|(row, row_vec)| {
                     row_vec
                         .iter()
                         .enumerate()
-                        .map(|(col, &v)| match v {
-                            false => false,
-                            true if count_adjacent(row as u16, col as u16, &lines) < 4 => false,
-                            true => true,
-                        })
+                        .map(|(col, &v)| v && count_adjacent(row as u16, col as u16, &lines) >= 4)
                         .collect()
                 })
                 .collect();
-
-            let count_after = count_total_true(&lines);
-
-            count += count_before - count_after;
-            should_continue = count_after != count_before;
+            if next == lines {
+                return before - total(&lines);
+            }
+            lines = next;
         }
-
-        Ok(count.to_string())
     }
 }
 
-fn count_total_true(grid: &Vec<Vec<bool>>) -> i32 {
-    grid.iter()
-        .map(|r| r.iter().filter(|&&v| v).count() as i32)
-        .sum::<i32>()
-}
-
-fn convert_input(input: &str) -> Vec<Vec<bool>> {
-    input
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|c| match c {
-                    '@' => true,
-                    _ => false,
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect()
-}
-const DELTAS: [(isize, isize); 8] = [
-    (-1, -1),
-    (-1, 0),
-    (-1, 1),
-    (0, -1),
-    (0, 1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
-];
-
-fn count_adjacent(row: u16, col: u16, grid: &Vec<Vec<bool>>) -> u16 {
-    DELTAS
-        .iter()
-        .filter_map(|(deviance_row, deviance_column)| {
-            let adjacent_row = row as isize + deviance_row;
-            let adjacent_column = col as isize + deviance_column;
-
-            if adjacent_row < 0 || adjacent_column < 0 {
-                return None;
-            }
-
-            grid.get(adjacent_row as usize)
-                .and_then(|row: &Vec<bool>| row.get(adjacent_column as usize))
-                .copied()
-        })
-        .filter(|&v| v)
-        .count() as u16
-}
-
 #[cfg(test)]
 mod tests {
+    use super::reference::{convert_input, count_adjacent};
     use super::*;
 
     static INPUT: &str = "..@@.@@@@.
@@ -141,6 +645,334 @@ mod tests {
         assert_eq!(count_adjacent(0, 1, &grid), 3);
     }
 
+    /// A grid where about three cells in four hold a roll.
+    fn random_grid(seed: &mut u64, width: usize, height: usize) -> String {
+        (0..height)
+            .map(|_| {
+                let row: String = (0..width)
+                    .map(|_| {
+                        *seed = seed
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        if *seed >> 62 == 0 { '.' } else { '@' }
+                    })
+                    .collect();
+                row + "\n"
+            })
+            .collect()
+    }
+
+    #[test]
+    fn remove_all_matches_round_simulation() {
+        let mut seed: u64 = 4;
+        for size in [1, 2, 5, 17, 40] {
+            let input = random_grid(&mut seed, size, size);
+            let grid = BitGrid::parse(&input, |c| c == '@');
+            assert_eq!(
+                remove_all(&grid, &Rules::puzzle()).total(),
+                reference::remove_by_rounds(convert_input(&input)),
+                "{}",
+                input
+            );
+        }
+    }
+
+    /// Recomputes every count from scratch each round, for any rules.
+    fn remove_by_rounds_with(grid: &BitGrid, rules: &Rules) -> usize {
+        let mut present: Vec<bool> = (0..grid.width() * grid.height())
+            .map(|i| grid.at(i))
+            .collect();
+        loop {
+            let removable: Vec<usize> = (0..present.len())
+                .filter(|&i| {
+                    let count = grid
+                        .neighbours_with(i, &rules.offsets, rules.boundary)
+                        .filter(|&n| match n {
+                            Neighbour::Cell(j) => present[j],
+                            Neighbour::Outside => true,
+                        })
+                        .count();
+                    present[i] && rules.comparison.holds(count as u16, rules.threshold)
+                })
+                .collect();
+            if removable.is_empty() {
+                return grid.count_ones() - present.iter().filter(|&&v| v).count();
+            }
+            removable.iter().for_each(|&i| present[i] = false);
+        }
+    }
+
+    #[test]
+    fn remove_all_with_other_rules() {
+        let rules = [
+            Rules::new(
+                Neighbourhood::VonNeumann(2),
+                Comparison::Less,
+                5,
+                Mode::Simultaneous,
+            ),
+            Rules::new(
+                Neighbourhood::Moore(2),
+                Comparison::LessOrEqual,
+                12,
+                Mode::Simultaneous,
+            ),
+            Rules::new(
+                Neighbourhood::Custom(vec![(0, 1), (0, 2), (1, 1)]),
+                Comparison::Less,
+                2,
+                Mode::Simultaneous,
+            ),
+            Rules::new(
+                Neighbourhood::Moore(1),
+                Comparison::GreaterOrEqual,
+                7,
+                Mode::Simultaneous,
+            ),
+            Rules::new(
+                Neighbourhood::Moore(1),
+                Comparison::Equal,
+                3,
+                Mode::Simultaneous,
+            ),
+        ];
+        let mut seed: u64 = 35;
+        for rules in rules.iter().map(|r| r.as_ref().unwrap()) {
+            for size in [3, 12, 30] {
+                let input = random_grid(&mut seed, size, size);
+                let grid = BitGrid::parse(&input, |c| c == '@');
+                assert_eq!(
+                    remove_all(&grid, rules).total(),
+                    remove_by_rounds_with(&grid, rules),
+                    "{:?}\n{}",
+                    rules,
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn remove_all_with_boundaries() {
+        let mut seed: u64 = 37;
+        for boundary in [Boundary::Occupied, Boundary::Wrap, Boundary::Mirror] {
+            let rules = [
+                Rules::puzzle(),
+                Rules::new(
+                    Neighbourhood::Custom(vec![(0, 1), (0, 2), (1, 1), (-3, 0)]),
+                    Comparison::Less,
+                    3,
+                    Mode::Simultaneous,
+                )
+                .unwrap(),
+            ];
+            for rules in rules.map(|r| r.with_boundary(boundary)) {
+                for (width, height) in [(1, 1), (2, 5), (7, 3), (20, 20)] {
+                    let input = random_grid(&mut seed, width, height);
+                    let grid = BitGrid::parse(&input, |c| c == '@');
+                    assert_eq!(
+                        remove_all(&grid, &rules).total(),
+                        remove_by_rounds_with(&grid, &rules),
+                        "{:?}\n{}",
+                        rules,
+                        input
+                    );
+                }
+            }
+        }
+
+        // on a torus every roll of a full grid has eight neighbours
+        let full = BitGrid::parse("@@@\n@@@\n@@@", |c| c == '@');
+        let wrap = Rules::puzzle().with_boundary(Boundary::Wrap);
+        assert_eq!(remove_all(&full, &wrap).total(), 0);
+        assert_eq!(remove_all(&full, &Rules::puzzle()).total(), 9);
+        // with the outside full of rolls, only the roll in row 2, column 6 is
+        // still accessible in the example
+        let occupied = Rules::puzzle().with_boundary(Boundary::Occupied);
+        assert_eq!(
+            count_removable(&BitGrid::parse(INPUT, |c| c == '@'), &occupied),
+            1
+        );
+    }
+
+    #[test]
+    fn sequential_mode() {
+        let grid = BitGrid::parse(INPUT, |c| c == '@');
+        let sequential = Rules {
+            mode: Mode::Sequential,
+            ..Rules::puzzle()
+        };
+        assert_eq!(remove_all(&grid, &sequential).total(), 43);
+
+        // removing either roll of a pair leaves the other one without neighbours
+        let pair = BitGrid::parse("@@", |c| c == '@');
+        let exactly_one = Rules::new(
+            Neighbourhood::Moore(1),
+            Comparison::Equal,
+            1,
+            Mode::Simultaneous,
+        )
+        .unwrap();
+        assert_eq!(remove_all(&pair, &exactly_one).total(), 2);
+        let exactly_one = Rules {
+            mode: Mode::Sequential,
+            ..exactly_one
+        };
+        assert_eq!(remove_all(&pair, &exactly_one).total(), 1);
+    }
+
+    #[test]
+    fn history_test() {
+        let grid = BitGrid::parse(INPUT, |c| c == '@');
+        let history = remove_all_with_depth(&grid, &Rules::puzzle());
+        assert_eq!(history.rounds, vec![13, 12, 7, 5, 2, 1, 1, 1, 1]);
+        let without_depth = remove_all(&grid, &Rules::puzzle());
+        assert_eq!(without_depth.rounds, history.rounds);
+        assert_eq!(without_depth.depth, None);
+        assert!(without_depth.write_depth(&grid, &mut Vec::new()).is_err());
+
+        let mut depth = Vec::new();
+        history.write_depth(&grid, &mut depth).unwrap();
+        assert_eq!(
+            String::from_utf8(depth).unwrap(),
+            "..11.1121.
+134.2.2.32
+24578.1.33
+2.69@@..2.
+13.@@@@.21
+.24@@@@@.2
+.2.@.@.@@3
+1.4@@.@@@4
+.23@@@@@5.
+1.1.@@@.1.
+"
+        );
+
+        let mut rounds = Vec::new();
+        history.write_rounds(&grid, &mut rounds).unwrap();
+        let rounds = String::from_utf8(rounds).unwrap();
+        assert!(rounds.starts_with(&format!(
+            "Initial state:\n{}\nRemove 13 rolls of paper:\n",
+            INPUT
+        )));
+        assert!(rounds.contains(
+            "Remove 1 roll of paper:
+..........
+..........
+..........
+...x@@....
+"
+        ));
+        assert!(rounds.ends_with("\ntotal of 43 rolls of paper can be removed\n"));
+    }
+
+    #[test]
+    fn neighbourhood_offsets() {
+        assert_eq!(Neighbourhood::Moore(1).offsets(), MOORE.to_vec());
+        assert_eq!(Neighbourhood::Moore(2).offsets().len(), 24);
+        assert_eq!(
+            Neighbourhood::VonNeumann(1).offsets(),
+            vec![(-1, 0), (0, -1), (0, 1), (1, 0)]
+        );
+        assert_eq!(Neighbourhood::VonNeumann(2).offsets().len(), 12);
+        for neighbourhood in [
+            Neighbourhood::Moore(3),
+            Neighbourhood::VonNeumann(3),
+            Neighbourhood::Custom(vec![(0, 1), (2, 2)]),
+        ] {
+            assert_eq!(neighbourhood.size(), Some(neighbourhood.offsets().len()));
+        }
+    }
+
+    #[test]
+    fn large_neighbourhoods_are_rejected_before_listing() {
+        let rules = |neighbourhood| {
+            Rules::new(neighbourhood, Comparison::Less, 4, Mode::Simultaneous)
+                .map_err(|e| e.to_string())
+        };
+        assert!(rules(Neighbourhood::Moore(127)).is_ok());
+        assert_eq!(
+            rules(Neighbourhood::Moore(128)).unwrap_err(),
+            "neighbourhood too large: 66048 cells"
+        );
+        assert!(rules(Neighbourhood::VonNeumann(180)).is_ok());
+        assert!(rules(Neighbourhood::VonNeumann(181)).is_err());
+        // listing these offsets would run out of memory
+        assert!(rules(Neighbourhood::Moore(50_000)).is_err());
+        assert_eq!(
+            rules(Neighbourhood::Moore(usize::MAX)).unwrap_err(),
+            "neighbourhood too large: more cells than usize holds"
+        );
+    }
+
+    #[test]
+    fn rules_from_options() {
+        let options = Options::parse(Vec::<String>::new()).unwrap();
+        assert_eq!(Rules::from_options(&options).unwrap(), Rules::puzzle());
+
+        let options = Options::parse([
+            "--offsets",
+            "-1,0;1,0 0,1",
+            "--compare",
+            ">=",
+            "--threshold",
+            "2",
+            "--mode",
+            "sequential",
+        ])
+        .unwrap();
+        assert_eq!(
+            Rules::from_options(&options).unwrap(),
+            Rules {
+                offsets: vec![(-1, 0), (1, 0), (0, 1)],
+                comparison: Comparison::GreaterOrEqual,
+                threshold: 2,
+                mode: Mode::Sequential,
+                boundary: Boundary::Empty,
+            }
+        );
+        let options = Options::parse(["--boundary", "wrap"]).unwrap();
+        assert_eq!(
+            Rules::from_options(&options).unwrap(),
+            Rules::puzzle().with_boundary(Boundary::Wrap)
+        );
+
+        for bad in [
+            ["--neighbourhood", "hex"],
+            ["--compare", "~"],
+            ["--offsets", "1;2"],
+            ["--boundary", "klein"],
+        ] {
+            assert!(Rules::from_options(&Options::parse(bad).unwrap()).is_err());
+        }
+        for conflict in [
+            ["--offsets", "0,1", "--radius", "2"],
+            ["--offsets", "0,1", "--neighbourhood", "moore"],
+        ] {
+            assert_eq!(
+                Rules::from_options(&Options::parse(conflict).unwrap())
+                    .unwrap_err()
+                    .to_string(),
+                "--offsets cannot be combined with --neighbourhood or --radius"
+            );
+        }
+    }
+
+    #[test]
+    fn count_accessible_matches_reference() {
+        let mut seed: u64 = 34;
+        for (width, height) in [(1, 1), (3, 7), (63, 5), (64, 4), (65, 6), (130, 9)] {
+            let input = random_grid(&mut seed, width, height);
+            let grid = BitGrid::parse(&input, |c| c == '@');
+            assert_eq!(
+                count_accessible(&grid),
+                reference::count_accessible(&input),
+                "{}",
+                input
+            );
+        }
+    }
+
     #[test]
     /**
     The forklifts can only access a roll of paper if there are fewer than four rolls of paper in the eight adjacent positions.
@@ -157,6 +989,8 @@ mod tests {
      */
     fn part1_test() {
         assert_eq!(Day04.part1(INPUT).unwrap(), "13");
+        let grid = BitGrid::parse(INPUT, |c| c == '@');
+        assert_eq!(count_removable(&grid, &Rules::puzzle()), 13);
     }
 
     #[test]
@@ -1,5 +1,13 @@
-use crate::{AocResult, Day};
+use crate::big::BigUint;
+use crate::error::ParseError;
+use crate::{AocResult, Day, Options};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::Hash;
+use std::io::{BufRead, Write};
+use std::num::{IntErrorKind, ParseIntError};
 use std::ops::RangeInclusive;
+use std::str::FromStr;
 
 pub struct Day05;
 impl Day for Day05 {
@@ -8,110 +16,720 @@ impl Day for Day05 {
     }
 
     fn part1(&self, input: &str) -> AocResult<String> {
-        let (ranges, ingredients) = parse_input(input.lines().collect());
+        let (ranges, ingredients) = parse_input::<i64>(input.lines().collect())?;
 
-        let fresh_ingredients = ingredients
-            .iter()
-            .filter(|i| ranges.iter().any(|r| r.contains(i)));
-
-        Ok(fresh_ingredients.count().to_string())
+        Ok(count_fresh_ingredients(&ranges, &ingredients).to_string())
     }
 
     fn part2(&self, input: &str) -> AocResult<String> {
-        let (ranges, _) = parse_input(input.lines().collect());
+        let (ranges, _) = parse_input::<i64>(input.lines().collect())?;
 
-        let converted_ranges: Vec<_> = ranges
-            .into_iter()
-            .map(|r| Interval {
-                start: *r.start(),
-                end: *r.end(),
-            })
-            .collect();
-
-        let count = count_distinct(converted_ranges);
+        let count = FreshRanges::new(&ranges).count();
 
         Ok(count.to_string())
     }
+
+    fn run(
+        &self,
+        command: &str,
+        input: &str,
+        options: &Options,
+        out: &mut dyn Write,
+    ) -> AocResult<()> {
+        options.allow(if command == "report" {
+            &["ids", "format"]
+        } else {
+            &["ids"]
+        })?;
+        match options.value("ids").unwrap_or("i64") {
+            "i64" => run_with::<i64>(command, input, options, out),
+            "u64" => run_with::<u64>(command, input, options, out),
+            "u128" => run_with::<u128>(command, input, options, out),
+            other => Err(format!("unknown ID type: {} (expected i64, u64 or u128)", other).into()),
+        }
+    }
+}
+
+/// The day05 commands, with IDs read as `T`.
+fn run_with<T: Id>(
+    command: &str,
+    input: &str,
+    options: &Options,
+    out: &mut dyn Write,
+) -> AocResult<()> {
+    let (ranges, ingredients) = parse_input::<T>(input.lines().collect())?;
+    match command {
+        "fresh-ingredients" => writeln!(out, "{}", count_fresh_ingredients(&ranges, &ingredients))?,
+        "fresh-ids" => writeln!(out, "{}", FreshRanges::new(&ranges).count())?,
+        "lookup" => lookup(&FreshRanges::new(&ranges), std::io::stdin().lock(), out)?,
+        "report" => {
+            let report = Report::new(&ranges, &ingredients);
+            match options.value("format").unwrap_or("text") {
+                "text" => report.write_text(out)?,
+                "json" => report.write_json(out)?,
+                other => return Err(format!("unknown report format: {}", other).into()),
+            }
+        }
+        "edit" => {
+            let mut database = Database::new();
+            ranges
+                .iter()
+                .for_each(|r| database.add_range(Interval::from_range(r)));
+            ingredients
+                .iter()
+                .for_each(|&id| database.add_ingredient(id));
+            edit(&mut database, std::io::stdin().lock(), out)?
+        }
+        _ => {
+            return Err(format!(
+                "day05 has no command '{}' (available: fresh-ingredients, fresh-ids, lookup, report, edit)",
+                command
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// A type ingredient IDs can be read as.
+trait Id: Copy + Ord + Hash + Debug + Display + FromStr<Err = ParseIntError> {
+    fn successor(self) -> Option<Self>;
+
+    fn predecessor(self) -> Option<Self>;
+
+    /// How far the ID is from the smallest one, so that ranges can be measured
+    /// the same way whatever the type.
+    fn offset(self) -> u128;
+}
+
+impl Id for i64 {
+    fn successor(self) -> Option<Self> {
+        self.checked_add(1)
+    }
+
+    fn predecessor(self) -> Option<Self> {
+        self.checked_sub(1)
+    }
+
+    fn offset(self) -> u128 {
+        self.abs_diff(i64::MIN) as u128
+    }
+}
+
+impl Id for u64 {
+    fn successor(self) -> Option<Self> {
+        self.checked_add(1)
+    }
+
+    fn predecessor(self) -> Option<Self> {
+        self.checked_sub(1)
+    }
+
+    fn offset(self) -> u128 {
+        self as u128
+    }
+}
+
+impl Id for u128 {
+    fn successor(self) -> Option<Self> {
+        self.checked_add(1)
+    }
+
+    fn predecessor(self) -> Option<Self> {
+        self.checked_sub(1)
+    }
+
+    fn offset(self) -> u128 {
+        self
+    }
+}
+
+/// A reversed range, like `5-3`, holds no ingredient here, as it always has
+/// for part 1, though part 2 and the other commands read it swapped.
+fn count_fresh_ingredients<T: Id>(ranges: &[RangeInclusive<T>], ingredients: &[T]) -> usize {
+    let ranges: Vec<RangeInclusive<T>> = ranges.iter().filter(|r| !r.is_empty()).cloned().collect();
+    let fresh = FreshRanges::new(&ranges);
+    ingredients.iter().filter(|&&i| fresh.contains(i)).count()
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Interval {
-    start: i64,
-    end: i64, // inclusivo
+/// Reads one ingredient ID per line from `ids` and answers each with a line
+/// saying whether it is fresh or spoiled, as soon as it arrives. Lines that are
+/// not IDs are reported on stderr and skipped.
+fn lookup<T: Id>(fresh: &FreshRanges<T>, ids: impl BufRead, out: &mut dyn Write) -> AocResult<()> {
+    for line in ids.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match line.parse::<T>() {
+            Ok(id) if fresh.contains(id) => writeln!(out, "{} fresh", id)?,
+            Ok(id) => writeln!(out, "{} spoiled", id)?,
+            Err(_) => eprintln!("skipped '{}': not an ingredient ID", line),
+        }
+        out.flush()?;
+    }
+    Ok(())
 }
 
-impl Interval {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Interval<T> {
+    start: T,
+    end: T, // inclusivo
+}
+
+impl<T: Id> Interval<T> {
+    fn from_range(range: &RangeInclusive<T>) -> Self {
+        Interval {
+            start: *range.start(),
+            end: *range.end(),
+        }
+        .normalized()
+    }
+
     fn normalized(self) -> Self {
         if self.start <= self.end {
             self
         } else {
-            Self { start: self.end, end: self.start }
+            Self {
+                start: self.end,
+                end: self.start,
+            }
         }
     }
 
-    fn len_u128(self) -> u128 {
+    /// How many IDs the interval holds: up to 2^128, one more than fits in a
+    /// `u128`.
+    fn len(self) -> BigUint {
         // len inclusiva: end - start + 1 (start <= end)
-        (self.end as i128 - self.start as i128 + 1) as u128
+        BigUint::from(self.end.offset() - self.start.offset()) + BigUint::from(1u64)
     }
+
+    fn contains(self, id: T) -> bool {
+        self.start <= id && id <= self.end
+    }
+
+    /// IDs are JSON strings, as they do not all fit in a double.
+    fn json(self) -> String {
+        format!(
+            "{{\"start\": \"{}\", \"end\": \"{}\"}}",
+            self.start, self.end
+        )
+    }
+}
+
+impl<T: Display> Display for Interval<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// The fresh ranges merged into sorted, disjoint intervals, so that checking
+/// an ingredient is a binary search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FreshRanges<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T: Id> FreshRanges<T> {
+    fn new(ranges: &[RangeInclusive<T>]) -> Self {
+        // Normalizza e ordina
+        let mut intervals: Vec<Interval<T>> = ranges.iter().map(Interval::from_range).collect();
+        intervals.sort_unstable_by(|a, b| a.start.cmp(&b.start).then(a.end.cmp(&b.end)));
+
+        let mut merged: Vec<Interval<T>> = Vec::with_capacity(intervals.len());
+        for next in intervals {
+            match merged.last_mut() {
+                // overlap o contiguo?
+                Some(cur) if cur.end.successor().is_none_or(|after| next.start <= after) => {
+                    cur.end = cur.end.max(next.end);
+                }
+                _ => merged.push(next),
+            }
+        }
+        FreshRanges { intervals: merged }
+    }
+
+    fn contains(&self, id: T) -> bool {
+        let i = self.intervals.partition_point(|it| it.end < id);
+        self.intervals.get(i).is_some_and(|it| it.start <= id)
+    }
+
+    /// How many IDs are fresh.
+    fn count(&self) -> BigUint {
+        self.intervals.iter().map(|it| it.len()).sum()
+    }
+
+    /// The spoiled IDs between the lowest and the highest fresh one.
+    fn gaps(&self) -> Vec<Interval<T>> {
+        // merged intervals are never adjacent, so an ID lies between them
+        self.intervals
+            .windows(2)
+            .map(|w| Interval {
+                start: w[0].end.successor().unwrap(),
+                end: w[1].start.predecessor().unwrap(),
+            })
+            .collect()
+    }
+
+    /// From the lowest to the highest fresh ID.
+    fn span(&self) -> Option<Interval<T>> {
+        let (first, last) = (self.intervals.first()?, self.intervals.last()?);
+        Some(Interval {
+            start: first.start,
+            end: last.end,
+        })
+    }
+}
+
+/// What the `report` command prints, worked out once for both formats.
+struct Report<'a, T> {
+    ranges: &'a [RangeInclusive<T>],
+    fresh: FreshRanges<T>,
+    /// Every listed ingredient with the indices of the input ranges that
+    /// contain it.
+    ingredients: Vec<(T, Vec<usize>)>,
 }
 
-fn count_distinct(mut intervals: Vec<Interval>) -> u128 {
-    if intervals.is_empty() {
-        return 0;
+impl<'a, T: Id> Report<'a, T> {
+    /// The ranges are sorted by start, with the largest end up to each of
+    /// them, so an ingredient only looks at the ranges starting at or before
+    /// it, from the last one back until no earlier range reaches it. Spoiled
+    /// ingredients look at none.
+    fn new(ranges: &'a [RangeInclusive<T>], ingredients: &[T]) -> Self {
+        let fresh = FreshRanges::new(ranges);
+        let mut by_start: Vec<(Interval<T>, usize)> =
+            ranges.iter().map(Interval::from_range).zip(0..).collect();
+        by_start.sort_unstable_by_key(|(interval, _)| interval.start);
+        let reach: Vec<T> = by_start
+            .iter()
+            .scan(None, |reach: &mut Option<T>, (interval, _)| {
+                let end = reach.map_or(interval.end, |r| r.max(interval.end));
+                *reach = Some(end);
+                Some(end)
+            })
+            .collect();
+
+        let ingredients = ingredients
+            .iter()
+            .map(|&id| {
+                if !fresh.contains(id) {
+                    return (id, Vec::new());
+                }
+                let before = by_start.partition_point(|(interval, _)| interval.start <= id);
+                let mut containing: Vec<usize> = (0..before)
+                    .rev()
+                    .take_while(|&k| reach[k] >= id)
+                    .filter(|&k| by_start[k].0.contains(id))
+                    .map(|k| by_start[k].1)
+                    .collect();
+                containing.sort_unstable();
+                (id, containing)
+            })
+            .collect();
+        Report {
+            ranges,
+            fresh,
+            ingredients,
+        }
+    }
+
+    /// The input range at `index` as written, with its line number.
+    fn range(&self, index: usize) -> (usize, &RangeInclusive<T>) {
+        (index + 1, &self.ranges[index])
     }
 
-    // Normalizza e ordina
-    for it in &mut intervals {
-        *it = it.normalized();
+    /// IDs counted once per range containing them, so overlaps count twice.
+    fn listed(&self) -> BigUint {
+        self.ranges
+            .iter()
+            .map(|r| Interval::from_range(r).len())
+            .sum()
     }
-    intervals.sort_unstable_by(|a, b| a.start.cmp(&b.start).then(a.end.cmp(&b.end)));
 
-    let mut total: u128 = 0;
+    fn fresh_ingredients(&self) -> usize {
+        self.ingredients
+            .iter()
+            .filter(|(_, containing)| !containing.is_empty())
+            .count()
+    }
 
-    let mut cur = intervals[0];
-    for next in intervals.into_iter().skip(1) {
-        // overlap o contiguo?
-        if next.start <= cur.end.saturating_add(1) {
-            if next.end > cur.end {
-                cur.end = next.end;
+    fn write_text(&self, out: &mut dyn Write) -> AocResult<()> {
+        writeln!(out, "fresh ranges:")?;
+        for interval in &self.fresh.intervals {
+            writeln!(out, "  {}", interval)?;
+        }
+        writeln!(out, "gaps:")?;
+        for gap in self.fresh.gaps() {
+            writeln!(out, "  {} ({} IDs)", gap, gap.len())?;
+        }
+        writeln!(out, "ingredients:")?;
+        for (id, containing) in &self.ingredients {
+            if containing.is_empty() {
+                writeln!(out, "  {} spoiled", id)?;
+                continue;
             }
+            let ranges: Vec<String> = containing
+                .iter()
+                .map(|&r| {
+                    let (line, range) = self.range(r);
+                    format!("{}-{} (line {})", range.start(), range.end(), line)
+                })
+                .collect();
+            writeln!(out, "  {} fresh: {}", id, ranges.join(", "))?;
+        }
+
+        let (covered, listed) = (self.fresh.count(), self.listed());
+        writeln!(
+            out,
+            "ranges: {}, merged into {}",
+            self.ranges.len(),
+            self.fresh.intervals.len()
+        )?;
+        writeln!(out, "fresh IDs: {}", covered)?;
+        writeln!(
+            out,
+            "overlap: {} (range lengths add up to {})",
+            listed.clone() - &covered,
+            listed
+        )?;
+        if let Some(span) = self.fresh.span() {
+            writeln!(
+                out,
+                "span: {} ({} IDs, {} in gaps)",
+                span,
+                span.len(),
+                span.len() - &covered
+            )?;
+        }
+        writeln!(
+            out,
+            "fresh ingredients: {} of {}",
+            self.fresh_ingredients(),
+            self.ingredients.len()
+        )?;
+        Ok(())
+    }
+
+    /// IDs and counts of IDs are written as strings: they reach 2^128, far
+    /// past the integers a double holds exactly. Line numbers and counts of
+    /// ranges or ingredients stay numbers.
+    fn write_json(&self, out: &mut dyn Write) -> AocResult<()> {
+        let intervals = |list: &[Interval<T>]| -> String {
+            let items: Vec<String> = list.iter().map(|it| it.json()).collect();
+            format!("[{}]", items.join(", "))
+        };
+        let ingredients: Vec<String> = self
+            .ingredients
+            .iter()
+            .map(|(id, containing)| {
+                let ranges: Vec<String> = containing
+                    .iter()
+                    .map(|&r| {
+                        let (line, range) = self.range(r);
+                        format!(
+                            "{{\"line\": {}, \"start\": \"{}\", \"end\": \"{}\"}}",
+                            line,
+                            range.start(),
+                            range.end()
+                        )
+                    })
+                    .collect();
+                format!(
+                    "    {{\"id\": \"{}\", \"fresh\": {}, \"ranges\": [{}]}}",
+                    id,
+                    !containing.is_empty(),
+                    ranges.join(", ")
+                )
+            })
+            .collect();
+        let (covered, listed) = (self.fresh.count(), self.listed());
+        let span = self.fresh.span();
+
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"merged\": {},", intervals(&self.fresh.intervals))?;
+        writeln!(out, "  \"gaps\": {},", intervals(&self.fresh.gaps()))?;
+        if ingredients.is_empty() {
+            writeln!(out, "  \"ingredients\": [],")?;
         } else {
-            total += cur.len_u128();
-            cur = next;
+            writeln!(
+                out,
+                "  \"ingredients\": [\n{}\n  ],",
+                ingredients.join(",\n")
+            )?;
         }
+        writeln!(out, "  \"stats\": {{")?;
+        writeln!(out, "    \"ranges\": {},", self.ranges.len())?;
+        writeln!(out, "    \"merged\": {},", self.fresh.intervals.len())?;
+        writeln!(out, "    \"fresh_ids\": \"{}\",", covered)?;
+        writeln!(out, "    \"listed_ids\": \"{}\",", listed)?;
+        writeln!(out, "    \"overlap\": \"{}\",", listed.clone() - &covered)?;
+        writeln!(
+            out,
+            "    \"span\": {},",
+            span.map_or("null".to_string(), |s| s.json())
+        )?;
+        writeln!(
+            out,
+            "    \"span_ids\": \"{}\",",
+            span.map_or(BigUint::zero(), |s| s.len())
+        )?;
+        writeln!(
+            out,
+            "    \"gap_ids\": \"{}\",",
+            span.map_or(BigUint::zero(), |s| s.len() - &covered)
+        )?;
+        writeln!(
+            out,
+            "    \"fresh_ingredients\": {},",
+            self.fresh_ingredients()
+        )?;
+        writeln!(out, "    \"ingredients\": {}", self.ingredients.len())?;
+        writeln!(out, "  }}")?;
+        writeln!(out, "}}")?;
+        Ok(())
     }
-    total += cur.len_u128();
-    total
 }
 
+/// Applies one command per line from `commands` to `database`, printing both
+/// answers after each: `add a-b` and `remove a-b` edit the fresh ranges,
+/// `ingredient id` lists one more ingredient. Bad commands are reported on
+/// stderr and skipped.
+fn edit<T: Id>(
+    database: &mut Database<T>,
+    commands: impl BufRead,
+    out: &mut dyn Write,
+) -> AocResult<()> {
+    database.write_counts(out)?;
+    for line in commands.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match database.apply(line) {
+            Ok(()) => database.write_counts(out)?,
+            Err(err) => eprintln!("skipped '{}': {}", line, err),
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// The fresh ranges and the listed ingredients, kept up to date with both
+/// answers as ranges come and go.
+///
+/// The ID space is cut at every range bound into segments, each with the
+/// number of ranges covering it. Editing a range only visits the segments
+/// inside it, and only a segment that becomes covered or uncovered changes the
+/// answers: by its length, and by the ingredients that fall in it.
+#[derive(Debug)]
+struct Database<T> {
+    /// How many ranges cover the IDs from each cut up to the next one. IDs
+    /// before the first cut are not covered.
+    coverage: BTreeMap<Cut<T>, u32>,
+    /// How many times each range was added.
+    ranges: HashMap<Interval<T>, usize>,
+    /// How many times each ingredient is listed.
+    ingredients: BTreeMap<T, usize>,
+    fresh_ids: BigUint,
+    fresh_ingredients: usize,
+}
+
+/// Where a segment of the ID space starts: at an ID, or past the largest one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Cut<T> {
+    At(T),
+    End,
+}
+
+impl<T: Id> Database<T> {
+    fn new() -> Self {
+        Database {
+            coverage: BTreeMap::new(),
+            ranges: HashMap::new(),
+            ingredients: BTreeMap::new(),
+            fresh_ids: BigUint::zero(),
+            fresh_ingredients: 0,
+        }
+    }
+
+    fn apply(&mut self, command: &str) -> Result<(), String> {
+        let (verb, arg) = command
+            .split_once(char::is_whitespace)
+            .ok_or("expected 'add a-b', 'remove a-b' or 'ingredient id'")?;
+        let arg = arg.trim();
+        let range = || -> Result<Interval<T>, String> {
+            parse_range(arg)
+                .map(|range| Interval::from_range(&range))
+                .map_err(|(_, reason)| reason.to_string())
+        };
+        match verb {
+            "add" => self.add_range(range()?),
+            "remove" => self.remove_range(range()?)?,
+            "ingredient" => self.add_ingredient(parse_id(arg)?),
+            _ => return Err(format!("unknown command '{}'", verb)),
+        }
+        Ok(())
+    }
+
+    fn add_range(&mut self, range: Interval<T>) {
+        *self.ranges.entry(range).or_default() += 1;
+        self.cover(range, true);
+    }
+
+    fn remove_range(&mut self, range: Interval<T>) -> Result<(), String> {
+        match self.ranges.get_mut(&range) {
+            Some(n) if *n > 1 => *n -= 1,
+            Some(_) => {
+                self.ranges.remove(&range);
+            }
+            None => return Err(format!("{} is not a fresh range", range)),
+        }
+        self.cover(range, false);
+        Ok(())
+    }
+
+    fn add_ingredient(&mut self, id: T) {
+        *self.ingredients.entry(id).or_default() += 1;
+        if self.coverage_before(Cut::At(id), true) > 0 {
+            self.fresh_ingredients += 1;
+        }
+    }
+
+    fn write_counts(&self, out: &mut dyn Write) -> AocResult<()> {
+        writeln!(
+            out,
+            "fresh ingredients: {}, fresh IDs: {}",
+            self.fresh_ingredients, self.fresh_ids
+        )?;
+        Ok(())
+    }
+
+    /// Adds one to (or takes one from) the coverage of every ID in `range`.
+    fn cover(&mut self, range: Interval<T>, add: bool) {
+        let start = Cut::At(range.start);
+        let end = range.end.successor().map_or(Cut::End, Cut::At);
+        self.cut(start);
+        self.cut(end);
+
+        let cuts: Vec<Cut<T>> = self.coverage.range(start..=end).map(|(&c, _)| c).collect();
+        for segment in cuts.windows(2) {
+            let count = self.coverage.get_mut(&segment[0]).unwrap();
+            let toggled = if add {
+                *count += 1;
+                *count == 1
+            } else {
+                *count -= 1;
+                *count == 0
+            };
+            if toggled {
+                // only the last segment ends at `Cut::End`, after the range
+                let ids = Interval {
+                    start: match segment[0] {
+                        Cut::At(id) => id,
+                        Cut::End => unreachable!(),
+                    },
+                    end: match segment[1] {
+                        Cut::At(id) => id.predecessor().unwrap(),
+                        Cut::End => range.end,
+                    },
+                };
+                let listed: usize = self
+                    .ingredients
+                    .range(ids.start..=ids.end)
+                    .map(|(_, &n)| n)
+                    .sum();
+                if add {
+                    self.fresh_ids += &ids.len();
+                    self.fresh_ingredients += listed;
+                } else {
+                    self.fresh_ids -= &ids.len();
+                    self.fresh_ingredients -= listed;
+                }
+            }
+        }
+
+        self.uncut(start);
+        self.uncut(end);
+    }
+
+    /// The coverage of the segment ending just before `at`, or the one
+    /// starting at it too if `inclusive`.
+    fn coverage_before(&self, at: Cut<T>, inclusive: bool) -> u32 {
+        let segment = if inclusive {
+            self.coverage.range(..=at).next_back()
+        } else {
+            self.coverage.range(..at).next_back()
+        };
+        segment.map_or(0, |(_, &count)| count)
+    }
+
+    fn cut(&mut self, at: Cut<T>) {
+        if !self.coverage.contains_key(&at) {
+            let count = self.coverage_before(at, false);
+            self.coverage.insert(at, count);
+        }
+    }
+
+    /// Drops the cut at `at` if the segments on both sides are covered alike.
+    fn uncut(&mut self, at: Cut<T>) {
+        if self.coverage.get(&at) == Some(&self.coverage_before(at, false)) {
+            self.coverage.remove(&at);
+        }
+    }
+}
 
-fn parse_input(input: Vec<&str>) -> (Vec<RangeInclusive<i64>>, Vec<i64>) {
-    let mut split = input
-        .splitn(2, |s| s.is_empty())
-        .map(|s| s.iter().collect::<Vec<_>>());
+/// Reads the fresh ranges, then, after a blank line, the ingredients. IDs are
+/// read as `T`; anything else is an error pointing at the line and column.
+fn parse_input<T: Id>(input: Vec<&str>) -> Result<(Vec<RangeInclusive<T>>, Vec<T>), ParseError> {
+    let mut lines = input.into_iter().enumerate();
 
-    let ranges: Vec<RangeInclusive<i64>> = split
-        .next()
-        .unwrap()
-        .into_iter()
-        .map(|i| str_to_inclusive_range(i))
-        .collect();
+    let mut ranges = Vec::new();
+    for (row, line) in lines.by_ref() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let range = parse_range(line)
+            .map_err(|(offset, reason)| ParseError::new(row, line, offset, line, reason))?;
+        ranges.push(range);
+    }
 
-    let ingredients: Vec<_> = split
-        .next()
-        .unwrap()
-        .into_iter()
-        .map(|i| i.parse::<i64>().unwrap())
-        .collect();
+    let mut ingredients = Vec::new();
+    for (row, line) in lines {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let id = parse_id(line).map_err(|reason| ParseError::new(row, line, 0, line, reason))?;
+        ingredients.push(id);
+    }
 
-    (ranges, ingredients)
+    Ok((ranges, ingredients))
 }
 
-fn str_to_inclusive_range(s: &str) -> RangeInclusive<i64> {
-    let mut it = s.split('-').map(|n| n.parse::<i64>().unwrap());
-    let start = it.next().unwrap();
-    let end = it.next().unwrap();
-    start..=end
+/// Splits a range at the first '-' after the start's sign, so that both bounds
+/// can be negative: `-5-10`, `-10--5`. On failure, gives the byte offset of the
+/// part in error and why.
+fn parse_range<T: Id>(text: &str) -> Result<RangeInclusive<T>, (usize, &'static str)> {
+    let split = text
+        .char_indices()
+        .skip(1)
+        .find(|&(_, c)| c == '-')
+        .map(|(i, _)| i)
+        .ok_or((0, "missing '-'"))?;
+    let start = parse_id(&text[..split]).map_err(|reason| (0, reason))?;
+    let end = parse_id(&text[split + 1..]).map_err(|reason| (split + 1, reason))?;
+    Ok(start..=end)
+}
+
+fn parse_id<T: Id>(text: &str) -> Result<T, &'static str> {
+    text.parse().map_err(|e: ParseIntError| match e.kind() {
+        IntErrorKind::Empty => "missing number",
+        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => "out of range for the ID type",
+        _ => "not a number",
+    })
 }
 
 #[cfg(test)]
@@ -131,13 +749,294 @@ mod tests {
 32
 ";
 
+    /// A pseudo-random number below `n`, from a linear congruential
+    /// generator stepping `seed`.
+    fn lcg(seed: &mut u64, n: u64) -> u64 {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (*seed >> 33) % n
+    }
+
     #[test]
     fn parse_input_test() {
         let expected = (
             vec![3..=5, 10..=14, 16..=20, 12..=18],
             vec![1, 5, 8, 11, 17, 32],
         );
-        assert_eq!(parse_input(INPUT.lines().collect()), expected);
+        assert_eq!(parse_input::<i64>(INPUT.lines().collect()), Ok(expected));
+    }
+
+    #[test]
+    fn parse_signed_and_wide_ids() {
+        assert_eq!(parse_range::<i64>("-5-10"), Ok(-5..=10));
+        assert_eq!(parse_range::<i64>("-10--5"), Ok(-10..=-5));
+        assert_eq!(parse_range::<i64>("5--3"), Ok(RangeInclusive::new(5, -3)));
+        assert_eq!(
+            parse_range::<u128>("18446744073709551616-340282366920938463463374607431768211455"),
+            Ok(1 << 64..=u128::MAX)
+        );
+        assert_eq!(parse_range::<u64>("-5-10"), Err((0, "not a number")));
+        assert_eq!(parse_range::<i64>("--5-3"), Err((0, "not a number")));
+        assert_eq!(parse_range::<i64>("5-"), Err((2, "missing number")));
+        assert_eq!(parse_range::<i64>("-5"), Err((0, "missing '-'")));
+    }
+
+    #[test]
+    fn parse_errors_are_located() {
+        let error = |input: &str| parse_input::<u64>(input.lines().collect()).unwrap_err();
+        assert_eq!(
+            error("3-5\n10-1x4\n\n1"),
+            ParseError {
+                line: 2,
+                column: 4,
+                text: "10-1x4".to_string(),
+                reason: "not a number",
+            }
+        );
+        assert_eq!(
+            error("3-5\n\n1\n\n-2").to_string(),
+            "'-2' at line 5, column 1: not a number"
+        );
+        assert_eq!(
+            error("1-18446744073709551616").to_string(),
+            "'1-18446744073709551616' at line 1, column 3: out of range for the ID type"
+        );
+        assert!(parse_input::<i64>(vec!["3 5"]).is_err());
+        assert_eq!(parse_input::<i64>(vec![]), Ok((vec![], vec![])));
+    }
+
+    #[test]
+    fn full_id_spaces() {
+        let everything = FreshRanges::new(&[0..=u128::MAX, 5..=7]);
+        assert_eq!(
+            everything.count().to_string(),
+            "340282366920938463463374607431768211456"
+        );
+        assert!(everything.gaps().is_empty());
+        assert_eq!(
+            FreshRanges::new(&[i64::MIN..=-1, 0..=i64::MAX])
+                .count()
+                .to_string(),
+            "18446744073709551616"
+        );
+
+        let mut database = Database::<u128>::new();
+        database.add_ingredient(u128::MAX);
+        database.add_range(Interval {
+            start: 1,
+            end: u128::MAX,
+        });
+        database.add_range(Interval { start: 0, end: 0 });
+        assert_eq!(database.fresh_ids, everything.count());
+        assert_eq!(database.fresh_ingredients, 1);
+        database
+            .remove_range(Interval {
+                start: 1,
+                end: u128::MAX,
+            })
+            .unwrap();
+        assert_eq!(database.fresh_ids, BigUint::from(1u64));
+        assert_eq!(database.fresh_ingredients, 0);
+        assert_eq!(database.coverage.len(), 2);
+    }
+
+    #[test]
+    fn fresh_ranges_test() {
+        let (ranges, _) = parse_input::<i64>(INPUT.lines().collect()).unwrap();
+        let fresh = FreshRanges::new(&ranges);
+        assert_eq!(
+            fresh.intervals,
+            vec![
+                Interval { start: 3, end: 5 },
+                Interval { start: 10, end: 20 }
+            ]
+        );
+        let found: Vec<i64> = (0..25).filter(|&i| fresh.contains(i)).collect();
+        let expected: Vec<i64> = (0..25)
+            .filter(|i| ranges.iter().any(|r| r.contains(i)))
+            .collect();
+        assert_eq!(found, expected);
+
+        // touching ranges merge, reversed ones are normalized
+        let fresh =
+            FreshRanges::new(&[7..=9, RangeInclusive::new(4, 1), 5..=6, i64::MAX..=i64::MAX]);
+        assert_eq!(fresh.intervals.len(), 2);
+        assert_eq!(fresh.count(), BigUint::from(10u64));
+        assert!(fresh.contains(i64::MAX) && !fresh.contains(0));
+        assert!(FreshRanges::<i64>::new(&[]).intervals.is_empty());
+    }
+
+    #[test]
+    fn reversed_ranges() {
+        // empty for part 1, as ever, but counted swapped by part 2
+        let input = "5-3\n10-12\n\n4\n11";
+        assert_eq!(Day05.part1(input).unwrap(), "1");
+        assert_eq!(Day05.part2(input).unwrap(), "6");
+    }
+
+    #[test]
+    fn lookup_test() {
+        let (ranges, _) = parse_input::<i64>(INPUT.lines().collect()).unwrap();
+        let mut out = Vec::new();
+        lookup(
+            &FreshRanges::new(&ranges),
+            "5\n\n 8 \nnope\n17\n".as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "5 fresh\n8 spoiled\n17 fresh\n"
+        );
+    }
+
+    #[test]
+    fn report_text() {
+        let (ranges, ingredients) = parse_input::<i64>(INPUT.lines().collect()).unwrap();
+        let mut out = Vec::new();
+        Report::new(&ranges, &ingredients)
+            .write_text(&mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "fresh ranges:
+  3-5
+  10-20
+gaps:
+  6-9 (4 IDs)
+ingredients:
+  1 spoiled
+  5 fresh: 3-5 (line 1)
+  8 spoiled
+  11 fresh: 10-14 (line 2)
+  17 fresh: 16-20 (line 3), 12-18 (line 4)
+  32 spoiled
+ranges: 4, merged into 2
+fresh IDs: 14
+overlap: 6 (range lengths add up to 20)
+span: 3-20 (18 IDs, 4 in gaps)
+fresh ingredients: 3 of 6
+"
+        );
+    }
+
+    #[test]
+    fn report_matches_scan() {
+        let mut seed: u64 = 39;
+        let mut next = |n: i64| lcg(&mut seed, n as u64) as i64;
+        for _ in 0..20 {
+            let ranges: Vec<RangeInclusive<i64>> = (0..next(30))
+                .map(|_| {
+                    let start = next(100);
+                    RangeInclusive::new(start, start + next(20) - 5)
+                })
+                .collect();
+            let ingredients: Vec<i64> = (0..40).map(|_| next(120) - 5).collect();
+            let report = Report::new(&ranges, &ingredients);
+            for (id, containing) in &report.ingredients {
+                let scanned: Vec<usize> = (0..ranges.len())
+                    .filter(|&r| Interval::from_range(&ranges[r]).contains(*id))
+                    .collect();
+                assert_eq!(containing, &scanned, "{} in {:?}", id, ranges);
+            }
+        }
+    }
+
+    #[test]
+    fn report_json() {
+        let (ranges, ingredients) = parse_input::<i64>(INPUT.lines().collect()).unwrap();
+        let mut out = Vec::new();
+        Report::new(&ranges, &ingredients)
+            .write_json(&mut out)
+            .unwrap();
+        let json = String::from_utf8(out).unwrap();
+        assert!(json.starts_with(
+            "{
+  \"merged\": [{\"start\": \"3\", \"end\": \"5\"}, {\"start\": \"10\", \"end\": \"20\"}],
+  \"gaps\": [{\"start\": \"6\", \"end\": \"9\"}],
+  \"ingredients\": [
+    {\"id\": \"1\", \"fresh\": false, \"ranges\": []},
+"
+        ));
+        assert!(json.contains(
+            "    {\"id\": \"17\", \"fresh\": true, \"ranges\": [{\"line\": 3, \"start\": \"16\", \"end\": \"20\"}, {\"line\": 4, \"start\": \"12\", \"end\": \"18\"}]},\n"
+        ));
+        assert!(json.contains("\"overlap\": \"6\","));
+        assert!(json.ends_with("\"ingredients\": 6\n  }\n}\n"));
+
+        let mut out = Vec::new();
+        Report::<i64>::new(&[], &[]).write_json(&mut out).unwrap();
+        let json = String::from_utf8(out).unwrap();
+        assert!(json.contains("\"ingredients\": [],\n") && json.contains("\"span\": null,"));
+
+        // past 2^53, where a double would round the ID
+        let ranges = [0..=u128::MAX];
+        let mut out = Vec::new();
+        Report::new(&ranges, &[(1 << 53) + 1])
+            .write_json(&mut out)
+            .unwrap();
+        let json = String::from_utf8(out).unwrap();
+        assert!(json.contains("{\"id\": \"9007199254740993\", \"fresh\": true,"));
+        assert!(json.contains("\"fresh_ids\": \"340282366920938463463374607431768211456\","));
+    }
+
+    #[test]
+    fn database_matches_recount() {
+        let mut database = Database::<i64>::new();
+        let mut ranges: Vec<RangeInclusive<i64>> = Vec::new();
+        let mut ingredients = Vec::new();
+        let mut seed: u64 = 40;
+        let mut next = |n: u64| lcg(&mut seed, n);
+        for _ in 0..500 {
+            match next(4) {
+                0 | 1 => {
+                    let start = next(60) as i64 - 10;
+                    let range = start..=start + next(12) as i64;
+                    database.add_range(Interval::from_range(&range));
+                    ranges.push(range);
+                }
+                2 if !ranges.is_empty() => {
+                    let range = ranges.swap_remove(next(ranges.len() as u64) as usize);
+                    database.remove_range(Interval::from_range(&range)).unwrap();
+                }
+                _ => {
+                    let id = next(80) as i64 - 15;
+                    database.add_ingredient(id);
+                    ingredients.push(id);
+                }
+            }
+
+            let fresh = FreshRanges::new(&ranges);
+            assert_eq!(database.fresh_ids, fresh.count());
+            assert_eq!(
+                database.fresh_ingredients,
+                ingredients.iter().filter(|&&i| fresh.contains(i)).count()
+            );
+        }
+        assert!(database.coverage.len() <= 2 * ranges.len());
+    }
+
+    #[test]
+    fn edit_test() {
+        let mut database = Database::<i64>::new();
+        let mut out = Vec::new();
+        let commands = "add 3-5\nadd 10-14\nadd 16-20\nadd 12-18
+ingredient 17\nremove 12-18\nremove 12-18\nfly away\nadd 9223372036854775807-9223372036854775807
+";
+        edit(&mut database, commands.as_bytes(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "fresh ingredients: 0, fresh IDs: 0
+fresh ingredients: 0, fresh IDs: 3
+fresh ingredients: 0, fresh IDs: 8
+fresh ingredients: 0, fresh IDs: 13
+fresh ingredients: 0, fresh IDs: 14
+fresh ingredients: 1, fresh IDs: 14
+fresh ingredients: 1, fresh IDs: 13
+fresh ingredients: 1, fresh IDs: 14
+"
+        );
     }
 
     #[test]
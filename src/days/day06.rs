@@ -1,4 +1,10 @@
-use crate::{AocResult, Day};
+use crate::big::BigUint;
+use crate::error::ParseError;
+use crate::{AocResult, Day, Options};
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::ops::Range;
+use std::str::FromStr;
 
 pub struct Day06;
 impl Day for Day06 {
@@ -7,158 +13,746 @@ impl Day for Day06 {
     }
 
     fn part1(&self, input: &str) -> AocResult<String> {
-        let problems = parse_input(input);
-        let result = problems.iter().map(|p| p.resolve()).sum::<u64>();
+        let problems = parse_input(input)?;
+        let result = grand_total::<u64>(&problems, Reading::PART1, None)?;
         Ok(result.to_string())
     }
 
     fn part2(&self, input: &str) -> AocResult<String> {
-        let problems = parse_input(input);
-        let result = problems
-            .iter()
-            .map(|p| p.convert_to_cephalopod_math().resolve())
-            .sum::<u64>();
+        let problems = parse_input(input)?;
+        let result = grand_total::<u64>(&problems, Reading::PART2, None)?;
         Ok(result.to_string())
     }
-}
 
-fn parse_input(input: &str) -> Vec<Problem> {
-    // 1) righe non vuote, preservando spazi interni/finali
-    let mut lines: Vec<&str> = input.lines().filter(|l| !l.trim().is_empty()).collect();
-    if lines.is_empty() {
-        return vec![];
+    fn run(
+        &self,
+        command: &str,
+        input: &str,
+        options: &Options,
+        out: &mut dyn Write,
+    ) -> AocResult<()> {
+        if command == "import" {
+            options.allow(&["format"])?;
+            let separator = separator(options)?;
+            write!(out, "{}", render(&import(input, separator)?))?;
+            return Ok(());
+        }
+        let problems = parse_input(input)?;
+        match command {
+            "total" => {
+                options.allow(&["reading", "int"])?;
+                let reading = options.get("reading")?.unwrap_or(Reading::PART1);
+                let total = evaluate(&problems, reading, options, None)?;
+                writeln!(out, "{}", total)?;
+            }
+            "explain" => {
+                options.allow(&["reading", "int"])?;
+                let readings = match options.value("reading") {
+                    Some(name) => vec![(name, name.parse()?)],
+                    None => vec![("normal", Reading::PART1), ("cephalopod", Reading::PART2)],
+                };
+                for (name, reading) in readings {
+                    writeln!(out, "{} reading:", name)?;
+                    let mut equation = |problem: &Problem, result: &dyn Display| {
+                        writeln!(out, "{} = {}", problem.equation(reading), result)
+                    };
+                    let total = evaluate(&problems, reading, options, Some(&mut equation))?;
+                    writeln!(out, "grand total: {}", total)?;
+                }
+            }
+            "export" => {
+                options.allow(&["format", "reading", "int"])?;
+                let separator = separator(options)?;
+                let reading = options.get("reading")?.unwrap_or(Reading::PART1);
+                let operands = problems.iter().map(|p| p.operands(reading).count()).max();
+                let mut header = vec!["operator".to_string(), "result".to_string()];
+                header.extend((1..=operands.unwrap_or(0)).map(|i| format!("operand {}", i)));
+                writeln!(out, "{}", header.join(&separator.to_string()))?;
+                let mut row = |problem: &Problem, result: &dyn Display| {
+                    write!(out, "{}{}{}", problem.operator, separator, result)?;
+                    for operand in problem.operands(reading) {
+                        write!(out, "{}{}", separator, operand)?;
+                    }
+                    writeln!(out)
+                };
+                evaluate(&problems, reading, options, Some(&mut row))?;
+            }
+            _ => {
+                return Err(format!(
+                    "day06 has no command '{}' (available: total, explain, export, import)",
+                    command
+                )
+                .into());
+            }
+        }
+        Ok(())
     }
+}
 
-    // 2) ultima riga = operatori
-    let ops_line = lines.pop().unwrap();
-    let num_lines = lines;
+/// Called with every problem solved and its result.
+type Solved<'s> = &'s mut dyn FnMut(&Problem, &dyn Display) -> io::Result<()>;
 
-    // 3) posizioni operatori e operatori
-    let mut op_positions: Vec<usize> = Vec::new();
-    let mut ops: Vec<Operator> = Vec::new();
+/// The grand total of `problems` read in `reading`, in the integer type
+/// chosen by `--int`.
+fn evaluate(
+    problems: &[Problem],
+    reading: Reading,
+    options: &Options,
+    solved: Option<Solved>,
+) -> AocResult<String> {
+    Ok(match options.value("int").unwrap_or("u64") {
+        "u64" => grand_total::<u64>(problems, reading, solved)?.to_string(),
+        "u128" => grand_total::<u128>(problems, reading, solved)?.to_string(),
+        "big" => grand_total::<BigUint>(problems, reading, solved)?.to_string(),
+        other => {
+            return Err(format!(
+                "unknown integer type: {} (expected u64, u128 or big)",
+                other
+            )
+            .into());
+        }
+    })
+}
 
-    for (i, b) in ops_line.as_bytes().iter().enumerate() {
-        match *b as char {
-            '*' | '+' => {
-                op_positions.push(i);
-                // safe perché è un singolo byte ASCII
-                let s = &ops_line[i..i + 1];
-                ops.push(Operator::from(s));
-            }
-            _ => {}
+/// The sum of the results of `problems` read in `reading`, or which problem
+/// (numbered from 1, left to right) could not be solved. Every problem solved,
+/// up to the one that fails, is handed to `solved` with its result.
+fn grand_total<N: Number>(
+    problems: &[Problem],
+    reading: Reading,
+    mut solved: Option<Solved>,
+) -> AocResult<N> {
+    let mut total = N::zero();
+    for (i, problem) in problems.iter().enumerate() {
+        let result = problem
+            .resolve::<N>(reading)
+            .map_err(|reason| format!("problem {}: {}", i + 1, reason))?;
+        if let Some(solved) = solved.as_mut() {
+            solved(problem, &result)?;
         }
+        total = total
+            .checked_add(&result)
+            .ok_or_else(|| format!("grand total: {}", N::OVERFLOW))?;
+    }
+    Ok(total)
+}
+
+/// An integer type worksheets can be evaluated in.
+trait Number: Clone + Ord + Display {
+    /// Why a result does not fit.
+    const OVERFLOW: &'static str;
+
+    fn zero() -> Self;
+
+    /// A digit, or the base ten that digits are read in.
+    fn small(n: u8) -> Self;
+
+    fn is_zero(&self) -> bool;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    /// The quotient and the remainder; `other` is not zero.
+    fn div_rem(&self, other: &Self) -> (Self, Self);
+
+    fn checked_pow(&self, exp: &Self) -> Option<Self>;
+}
+
+impl Number for u64 {
+    const OVERFLOW: &'static str = "u64 overflow";
+
+    fn zero() -> Self {
+        0
+    }
+
+    fn small(n: u8) -> Self {
+        n.into()
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        u64::checked_sub(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u64::checked_mul(*self, *other)
     }
 
-    let mut widths: Vec<usize> = Vec::with_capacity(op_positions.len());
-    for w in op_positions.windows(2) {
-        let a = w[0];
-        let b = w[1];
-        widths.push(b - a - 1);
+    fn div_rem(&self, other: &Self) -> (Self, Self) {
+        (self / other, self % other)
     }
 
-    let last_width = if widths.is_empty() {
+    fn checked_pow(&self, exp: &Self) -> Option<Self> {
+        if *self <= 1 && *exp > 0 {
+            return Some(*self);
+        }
+        u64::checked_pow(*self, u32::try_from(*exp).ok()?)
+    }
+}
+
+impl Number for u128 {
+    const OVERFLOW: &'static str = "u128 overflow";
+
+    fn zero() -> Self {
         0
-    } else {
-        *widths.last().unwrap()
-    };
-    widths.push(last_width);
+    }
+
+    fn small(n: u8) -> Self {
+        n.into()
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u128::checked_add(*self, *other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        u128::checked_sub(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u128::checked_mul(*self, *other)
+    }
+
+    fn div_rem(&self, other: &Self) -> (Self, Self) {
+        (self / other, self % other)
+    }
 
-    fn take_segment(line: &str, start: usize, width: usize) -> String {
-        if width == 0 {
-            return String::new();
+    fn checked_pow(&self, exp: &Self) -> Option<Self> {
+        if *self <= 1 && *exp > 0 {
+            return Some(*self);
         }
-        let bytes = line.as_bytes();
-        let end = start.saturating_add(width);
+        u128::checked_pow(*self, u32::try_from(*exp).ok()?)
+    }
+}
 
-        let mut out = if start >= bytes.len() {
-            String::new()
-        } else {
-            let e = end.min(bytes.len());
-            // ASCII-only → slicing by bytes ok
-            line[start..e].to_string()
-        };
+/// Exact, except for powers of over 300,000 digits, too slow to evaluate.
+impl Number for BigUint {
+    const OVERFLOW: &'static str = "result too large to evaluate";
+
+    fn zero() -> Self {
+        BigUint::zero()
+    }
+
+    fn small(n: u8) -> Self {
+        BigUint::from(u64::from(n))
+    }
+
+    fn is_zero(&self) -> bool {
+        BigUint::is_zero(self)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self.clone() + other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        (self >= other).then(|| self.clone() - other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn div_rem(&self, other: &Self) -> (Self, Self) {
+        BigUint::div_rem(self, other).unwrap()
+    }
 
-        // pad a destra se la riga è corta (così la "cella" rimane di width fissa)
-        if out.len() < width {
-            out.push_str(&" ".repeat(width - out.len()));
+    fn checked_pow(&self, exp: &Self) -> Option<Self> {
+        /// Multiplying is quadratic: a power this long takes about a second
+        /// to compute and print, and one ten times longer a hundred seconds.
+        const MAX_DIGITS: f64 = 300_000.0;
+        if exp.is_zero() {
+            return Some(BigUint::from(1u64));
         }
-        out
+        if *self <= BigUint::from(1u64) {
+            return Some(self.clone());
+        }
+        let exp = exp.to_u128()?;
+        if self.log10() * exp as f64 > MAX_DIGITS {
+            return None;
+        }
+        Some(self.pow(exp as u32))
+    }
+}
+
+/// The worksheet as a grid over the input: its non-blank lines, the operators
+/// last, with their line numbers counted from 0. Only ASCII is accepted, so
+/// that a byte is one column, both here and on screen.
+struct Sheet<'a> {
+    rows: Vec<(usize, &'a str)>,
+    width: usize,
+}
+impl<'a> Sheet<'a> {
+    fn new(input: &'a str) -> Result<Self, ParseError> {
+        let mut rows = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            if let Some(offset) = line.find(|c: char| !c.is_ascii()) {
+                let c = line[offset..].chars().next().unwrap();
+                return Err(ParseError::new(
+                    i,
+                    line,
+                    offset,
+                    &c.to_string(),
+                    "not ASCII, so columns may not line up",
+                ));
+            }
+            // righe non vuote, preservando spazi interni/finali
+            if !line.trim().is_empty() {
+                rows.push((i, line));
+            }
+        }
+        let width = rows.iter().map(|(_, r)| r.len()).max().unwrap_or(0);
+        Ok(Sheet { rows, width })
     }
 
-    (0..ops.len())
-        .map(|col| {
-            let start = op_positions[col];
-            let width = widths[col];
+    /// The spans of columns holding a problem each: between them are the
+    /// separators, all spaces (or past the end) on every line, operators
+    /// included.
+    fn problem_columns(&self) -> Vec<Range<usize>> {
+        let is_separator = |c: usize| self.rows.iter().all(|(_, r)| byte(r, c) == b' ');
+        let mut columns = Vec::new();
+        let mut start = None;
+        for c in 0..=self.width {
+            match (start, c < self.width && !is_separator(c)) {
+                (None, true) => start = Some(c),
+                (Some(s), false) => {
+                    columns.push(s..c);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        columns
+    }
+}
+
+/// The byte in column `col` of `line`, a space past its end.
+fn byte(line: &str, col: usize) -> u8 {
+    line.as_bytes().get(col).copied().unwrap_or(b' ')
+}
+
+/// The text in columns `cols` of `line`, cut short where it ends.
+fn slice(line: &str, cols: Range<usize>) -> &str {
+    &line[cols.start.min(line.len())..cols.end.min(line.len())]
+}
+
+fn parse_input(input: &str) -> Result<Vec<Problem<'_>>, ParseError> {
+    let sheet = Sheet::new(input)?;
+    let Some(((ops_row, ops_line), num_lines)) = sheet.rows.split_last() else {
+        return Ok(vec![]);
+    };
+    let columns = sheet.problem_columns();
 
-            let numbers = num_lines
+    // ultima riga = operatori, uno per colonna, allineato come capita
+    let error = |col: usize, text: &str, reason| ParseError {
+        line: ops_row + 1,
+        column: col + 1,
+        text: text.to_string(),
+        reason,
+    };
+    let mut ops: Vec<Operator> = Vec::with_capacity(columns.len());
+    for cols in &columns {
+        let mut symbols = slice(ops_line, cols.clone())
+            .split(' ')
+            .scan(cols.start, |col, word| {
+                let symbol = (*col, word);
+                *col += word.len() + 1;
+                Some(symbol)
+            })
+            .filter(|(_, word)| !word.is_empty());
+        let Some((col, symbol)) = symbols.next() else {
+            let number = num_lines
+                .first()
+                .map_or("", |(_, r)| slice(r, cols.clone()));
+            return Err(error(
+                cols.start,
+                number.trim(),
+                "no operator under this column",
+            ));
+        };
+        if let Some((col, extra)) = symbols.next() {
+            return Err(error(col, extra, "more than one operator in this column"));
+        }
+        ops.push(
+            symbol
+                .parse()
+                .map_err(|reason| error(col, symbol, reason))?,
+        );
+    }
+
+    Ok(columns
+        .into_iter()
+        .zip(ops)
+        .map(|(cols, operator)| Problem {
+            cells: num_lines
                 .iter()
-                .map(|line| take_segment(line, start, width))
-                .collect::<Vec<_>>();
+                .map(|(_, line)| slice(line, cols.clone()))
+                .collect(),
+            operator,
+        })
+        .collect())
+}
+
+/// The field separator chosen by `--format csv|tsv` (default `csv`).
+fn separator(options: &Options) -> AocResult<char> {
+    match options.value("format").unwrap_or("csv") {
+        "csv" => Ok(','),
+        "tsv" => Ok('\t'),
+        other => Err(format!("unknown table format: {} (expected csv or tsv)", other).into()),
+    }
+}
 
-            Problem {
-                numbers,
-                operator: ops[col].clone(),
+/// Problems from a table as written by `export`: an operator, a result
+/// (ignored) and the operands on every row, after an optional header. Empty
+/// fields are skipped, so rows may be padded to the longest one.
+fn import(input: &str, separator: char) -> Result<Vec<Problem<'_>>, ParseError> {
+    let mut problems = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut fields = line.split(separator).scan(1, |column, field| {
+            let indent = field.chars().take_while(|c| c.is_whitespace()).count();
+            let start = *column + indent;
+            *column += field.chars().count() + 1;
+            Some((start, field.trim()))
+        });
+        let (column, operator) = fields.next().unwrap();
+        if i == 0 && operator == "operator" {
+            continue;
+        }
+        let error = |column, text: &str, reason| ParseError {
+            line: i + 1,
+            column,
+            text: text.to_string(),
+            reason,
+        };
+        let operator = operator
+            .parse()
+            .map_err(|reason| error(column, operator, reason))?;
+        let mut cells = Vec::new();
+        for (column, operand) in fields.skip(1).filter(|(_, f)| !f.is_empty()) {
+            if !operand.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(error(column, operand, "operands must be written in digits"));
             }
-        })
+            cells.push(operand);
+        }
+        problems.push(Problem { cells, operator });
+    }
+    Ok(problems)
+}
+
+/// The worksheet `parse_input` reads `problems` from: their operands right
+/// aligned, one per line, and the operators on the last line.
+fn render(problems: &[Problem]) -> String {
+    let height = problems.iter().map(|p| p.rows().count()).max();
+    let mut lines = vec![String::new(); height.unwrap_or(0) + 1];
+    for (i, problem) in problems.iter().enumerate() {
+        let operands: Vec<&str> = problem.rows().collect();
+        let symbol = problem.operator.symbol();
+        let width = operands.iter().map(|o| o.len()).max().unwrap_or(0);
+        let width = width.max(symbol.len());
+        let (operators, numbers) = lines.split_last_mut().unwrap();
+        for (row, line) in numbers.iter_mut().enumerate() {
+            if i > 0 {
+                line.push(' ');
+            }
+            line.push_str(&format!("{:>width$}", operands.get(row).unwrap_or(&"")));
+        }
+        if i > 0 {
+            operators.push(' ');
+        }
+        operators.push_str(&format!("{:<width$}", symbol));
+    }
+    lines
+        .iter()
+        .map(|line| format!("{}\n", line.trim_end()))
         .collect()
 }
 
+/// How the numbers of a problem are combined. Operands are taken in reading
+/// order and folded from the left, so `a - b - c` is `(a - b) - c` and
+/// `a ^ b ^ c` is `(a ^ b) ^ c`.
 #[derive(Debug, PartialEq, Clone)]
 enum Operator {
     Multiply,
     Add,
+    Subtract,
+    Divide,
+    Remainder,
+    Power,
+    Min,
+    Max,
 }
 impl Operator {
-    fn apply(&self, a: &u64, b: &u64) -> u64 {
+    fn apply<N: Number>(&self, a: N, b: N) -> Result<N, &'static str> {
+        match self {
+            Self::Multiply => a.checked_mul(&b).ok_or(N::OVERFLOW),
+            Self::Add => a.checked_add(&b).ok_or(N::OVERFLOW),
+            Self::Subtract => a.checked_sub(&b).ok_or("negative result"),
+            Self::Divide | Self::Remainder if b.is_zero() => Err("division by zero"),
+            Self::Divide => Ok(a.div_rem(&b).0),
+            Self::Remainder => Ok(a.div_rem(&b).1),
+            Self::Power => a.checked_pow(&b).ok_or(N::OVERFLOW),
+            Self::Min => Ok(a.min(b)),
+            Self::Max => Ok(a.max(b)),
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
         match self {
-            Self::Multiply => a * b,
-            Self::Add => a + b,
+            Self::Multiply => "*",
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Divide => "/",
+            Self::Remainder => "%",
+            Self::Power => "^",
+            Self::Min => "min",
+            Self::Max => "max",
         }
     }
 }
-impl From<&str> for Operator {
-    fn from(value: &str) -> Self {
-        match value {
-            "*" => Self::Multiply,
-            "+" => Self::Add,
-            _ => panic!("invalid operator: {}", value),
+impl FromStr for Operator {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "*" => Ok(Self::Multiply),
+            "+" => Ok(Self::Add),
+            "-" => Ok(Self::Subtract),
+            "/" => Ok(Self::Divide),
+            "%" => Ok(Self::Remainder),
+            "^" => Ok(Self::Power),
+            "min" => Ok(Self::Min),
+            "max" => Ok(Self::Max),
+            _ => Err("unknown operator (expected + * - / % ^ min max)"),
         }
     }
 }
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+/// A column of the worksheet: its cells, top to bottom, borrowed from the
+/// input as written (those on short lines are cut short). Its numbers are
+/// read from the cells in place, in any `Reading`.
 #[derive(Debug, PartialEq, Clone)]
-struct Problem {
-    numbers: Vec<String>,
+struct Problem<'a> {
+    cells: Vec<&'a str>,
     operator: Operator,
 }
-impl Problem {
-    fn resolve(&self) -> u64 {
-        let result = self
-            .numbers
+impl<'a> Problem<'a> {
+    fn resolve<N: Number>(&self, reading: Reading) -> Result<N, String> {
+        let mut numbers = self.operands(reading).map(Digits::parse::<N>);
+        let Some(first) = numbers.next() else {
+            return Ok(N::zero());
+        };
+        numbers.try_fold(first?, |acc, n| Ok(self.operator.apply(acc, n?)?))
+    }
+
+    /// The numbers in `reading`, in order. Blank ones, left by ragged lines,
+    /// are not operands.
+    fn operands(&self, reading: Reading) -> impl Iterator<Item = Digits<'_>> {
+        let height = self.cells.len();
+        let width = self.cells.iter().map(|c| c.len()).max().unwrap_or(0);
+        let count = match reading {
+            Reading::Rows => height,
+            Reading::Columns { .. } => width,
+            Reading::Diagonal => (width + height).saturating_sub(1),
+        };
+        (0..count)
+            .map(move |k| {
+                let path = match reading {
+                    Reading::Rows => Path::Row(k),
+                    Reading::Columns {
+                        from_right,
+                        upwards,
+                    } => Path::Column {
+                        col: if from_right { width - 1 - k } else { k },
+                        upwards,
+                    },
+                    Reading::Diagonal => Path::Diagonal {
+                        offset: k as isize + 1 - height as isize,
+                    },
+                };
+                Digits {
+                    cells: &self.cells,
+                    width,
+                    path,
+                }
+            })
+            .filter(|digits| !digits.is_blank())
+    }
+
+    /// The non-blank cells, trimmed: the operands read in rows.
+    fn rows(&self) -> impl Iterator<Item = &'a str> {
+        self.cells
             .iter()
-            .map(|s| s.trim().parse::<u64>().unwrap())
-            .reduce(|acc, i| self.operator.apply(&acc, &i));
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// The operands in `reading` joined by the operator, as in `123 * 45 * 6`.
+    fn equation(&self, reading: Reading) -> Equation<'_, 'a> {
+        Equation {
+            problem: self,
+            reading,
+        }
+    }
+}
 
-        result.unwrap_or(0)
+struct Equation<'p, 'a> {
+    problem: &'p Problem<'a>,
+    reading: Reading,
+}
+impl Display for Equation<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, operand) in self.problem.operands(self.reading).enumerate() {
+            if i > 0 {
+                write!(f, " {} ", self.problem.operator)?;
+            }
+            write!(f, "{}", operand)?;
+        }
+        Ok(())
     }
+}
 
-    fn convert_to_cephalopod_math(&self) -> Problem {
-        let num_as_str: Vec<_> = self.numbers.iter().map(|n| n.to_string()).collect();
-        let max_length = num_as_str.iter().map(|s| s.len()).max().unwrap_or(0);
+/// One number of a problem as read: the bytes of its cells along `path`, a
+/// space where a cell is cut short.
+#[derive(Debug, Clone, Copy)]
+struct Digits<'p> {
+    cells: &'p [&'p str],
+    width: usize,
+    path: Path,
+}
 
-        let cephalopod_numbers: Vec<_> = (0..max_length)
-            .map(|i| {
-                num_as_str
-                    .iter()
-                    .map(|s| s.chars().nth(i).map(|c| vec![c]).unwrap_or_default())
-                    .flatten()
-                    .collect::<Vec<_>>()
-            })
-            .map(|chars| chars.iter().collect::<String>())
-            .collect();
+#[derive(Debug, Clone, Copy)]
+enum Path {
+    Row(usize),
+    Column {
+        col: usize,
+        upwards: bool,
+    },
+    /// Down-right through the cells in column `row + offset` of every row.
+    Diagonal {
+        offset: isize,
+    },
+}
+
+impl<'p> Digits<'p> {
+    fn bytes(self) -> impl Iterator<Item = u8> + 'p {
+        let height = self.cells.len();
+        let len = match self.path {
+            Path::Row(row) => self.cells[row].len(),
+            Path::Column { .. } | Path::Diagonal { .. } => height,
+        };
+        (0..len).filter_map(move |i| {
+            let (row, col) = match self.path {
+                Path::Row(row) => (row, i),
+                Path::Column { col, upwards } => (if upwards { height - 1 - i } else { i }, col),
+                Path::Diagonal { offset } => {
+                    let col = usize::try_from(i as isize + offset).ok();
+                    (i, col.filter(|&col| col < self.width)?)
+                }
+            };
+            Some(byte(self.cells[row], col))
+        })
+    }
+
+    fn is_blank(self) -> bool {
+        self.bytes().all(|b| b == b' ')
+    }
+
+    /// The number, ignoring the spaces around it.
+    fn parse<N: Number>(self) -> Result<N, String> {
+        let ten = N::small(10);
+        let mut n = N::zero();
+        let mut ended = false;
+        for b in self.bytes().skip_while(|&b| b == b' ') {
+            match b {
+                b' ' => ended = true,
+                b'0'..=b'9' if !ended => {
+                    n = n
+                        .checked_mul(&ten)
+                        .and_then(|n| n.checked_add(&N::small(b - b'0')))
+                        .ok_or(N::OVERFLOW)?;
+                }
+                _ => return Err(format!("'{}' is not a number", self)),
+            }
+        }
+        Ok(n)
+    }
+}
 
-        Problem {
-            numbers: cephalopod_numbers,
-            operator: self.operator.clone(),
+/// The bytes, without the spaces around them.
+impl Display for Digits<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut spaces = 0;
+        for b in self.bytes().skip_while(|&b| b == b' ') {
+            if b == b' ' {
+                spaces += 1;
+            } else {
+                write!(f, "{:spaces$}{}", "", b as char)?;
+                spaces = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How the digits of a problem are read into numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reading {
+    /// Every line is a number, and they come top to bottom.
+    Rows,
+    /// Every column is a number, its digits read downwards or `upwards`. The
+    /// columns come from the right, as cephalopods read, or from the left.
+    Columns { from_right: bool, upwards: bool },
+    /// Every down-right diagonal is a number read downwards, the diagonals
+    /// coming from the bottom-left corner to the top-right one.
+    Diagonal,
+}
+impl Reading {
+    const PART1: Reading = Reading::Rows;
+    const PART2: Reading = Reading::Columns {
+        from_right: true,
+        upwards: false,
+    };
+}
+impl FromStr for Reading {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let columns = |from_right, upwards| Reading::Columns {
+            from_right,
+            upwards,
+        };
+        match s {
+            "normal" | "part1" => Ok(Reading::PART1),
+            "cephalopod" | "part2" => Ok(Reading::PART2),
+            "cephalopod-up" => Ok(columns(true, true)),
+            "columns" => Ok(columns(false, false)),
+            "columns-up" => Ok(columns(false, true)),
+            "diagonal" => Ok(Reading::Diagonal),
+            _ => Err(format!(
+                "unknown reading: {} (expected normal, cephalopod, cephalopod-up, columns, columns-up or diagonal)",
+                s
+            )),
         }
     }
 }
@@ -175,47 +769,411 @@ mod tests {
     #[test]
     fn parse_input_test() {
         let p1: Problem = Problem {
-            numbers: vec!["123".to_string(), " 45".to_string(), "  6".to_string()],
+            cells: vec!["123", " 45", "  6"],
             operator: Operator::Multiply,
         };
         let p2: Problem = Problem {
-            numbers: vec!["328".to_string(), "64 ".to_string(), "98 ".to_string()],
+            cells: vec!["328", "64 ", "98 "],
             operator: Operator::Add,
         };
         let p3: Problem = Problem {
-            numbers: vec![" 51".to_string(), "387".to_string(), "215".to_string()],
+            cells: vec![" 51", "387", "215"],
             operator: Operator::Multiply,
         };
         let p4: Problem = Problem {
-            numbers: vec!["64 ".to_string(), "23 ".to_string(), "314".to_string()],
+            cells: vec!["64", "23", "314"],
             operator: Operator::Add,
         };
-        assert_eq!(parse_input(INPUT), vec![p1, p2, p3, p4]);
+        assert_eq!(parse_input(INPUT), Ok(vec![p1, p2, p3, p4]));
+    }
+
+    #[test]
+    fn operators() {
+        let solve = |input: &str| {
+            let problems = parse_input(input).unwrap();
+            let normal = problems
+                .iter()
+                .map(|p| p.resolve::<u64>(Reading::PART1))
+                .collect::<Vec<_>>();
+            let cephalopod = problems
+                .iter()
+                .map(|p| p.resolve::<u64>(Reading::PART2))
+                .collect::<Vec<_>>();
+            (normal, cephalopod)
+        };
+        // top to bottom, e.g. 100 - 20 - 3; right to left, e.g. 3 - 2 - 1
+        let (normal, cephalopod) = solve(
+            "100 600 497 200 420 420
+ 20  48  50   3 170 170
+  3   2  13   2   9 901
+-   /   %   ^   min max",
+        );
+        assert_eq!(
+            normal,
+            vec![Ok(77), Ok(6), Ok(8), Ok(64_000_000_000_000), Ok(9), Ok(901)]
+        );
+        // 3 - 2 - 1, 82 / 4 / 6, 703 % 951 % 4, 32 ^ 0 ^ 2, min, max
+        assert_eq!(cephalopod, vec![Ok(0), Ok(3), Ok(3), Ok(1), Ok(9), Ok(419)]);
+
+        let (normal, _) = solve("1 5\n2 0\n- %");
+        assert_eq!(
+            normal,
+            vec![
+                Err("negative result".to_string()),
+                Err("division by zero".to_string())
+            ]
+        );
+        assert_eq!(
+            Day06.part1("1 1\n2 5\n+ -").unwrap_err().to_string(),
+            "problem 2: negative result"
+        );
+    }
+
+    #[test]
+    fn powers_of_zero_and_one() {
+        // exponents past u32, and past u128 for big integers, still give 0 and 1
+        let input =
+            "1          0          0 2\n5000000000 5000000000 0 3\n^          ^          ^ ^";
+        for int in ["u64", "u128", "big"] {
+            let options = Options::parse(["--int", int]).unwrap();
+            let mut out = Vec::new();
+            Day06.run("total", input, &options, &mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), "10\n", "{}", int);
+        }
+        let huge = "1".repeat(50);
+        for base in ["0", "1"] {
+            let problem = Problem {
+                cells: vec![base, &huge],
+                operator: Operator::Power,
+            };
+            assert_eq!(
+                problem.resolve::<BigUint>(Reading::PART1),
+                Ok(base.parse().unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn huge_powers_fail_fast() {
+        let power = |base: &'static str, exp: &'static str| Problem {
+            cells: vec![base, exp],
+            operator: Operator::Power,
+        };
+        let start = std::time::Instant::now();
+        for exp in ["100000", "1000000", "4000000000"] {
+            assert_eq!(
+                power("999999999", exp).resolve::<BigUint>(Reading::PART1),
+                Err("result too large to evaluate".to_string())
+            );
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        // about 3,000 digits
+        let small = power("2", "10000").resolve::<BigUint>(Reading::PART1);
+        assert_eq!(small.unwrap().to_string().len(), 3011);
+        assert_eq!(
+            power("0", "0").resolve::<BigUint>(Reading::PART1),
+            Ok(BigUint::from(1u64))
+        );
+    }
+
+    #[test]
+    fn overflow_is_reported() {
+        let input = "4294967296 1
+4294967296 2
+*          *";
+        assert_eq!(
+            Day06.part1(input).unwrap_err().to_string(),
+            "problem 1: u64 overflow"
+        );
+        let run = |int: &str, reading: &str| {
+            let options = Options::parse(["--int", int, "--reading", reading]).unwrap();
+            let mut out = Vec::new();
+            Day06
+                .run("total", input, &options, &mut out)
+                .map(|()| String::from_utf8(out).unwrap())
+        };
+        assert_eq!(run("u128", "normal").unwrap(), "18446744073709551618\n");
+        assert_eq!(run("big", "normal").unwrap(), "18446744073709551618\n");
+
+        let huge = "18446744073709551616 3\n18446744073709551616 2\n*                    ^";
+        let options = Options::parse(["--int", "u128"]).unwrap();
+        assert_eq!(
+            Day06
+                .run("total", huge, &options, &mut Vec::new())
+                .unwrap_err()
+                .to_string(),
+            "problem 1: u128 overflow"
+        );
+        let options = Options::parse(["--int", "big"]).unwrap();
+        let mut out = Vec::new();
+        Day06.run("total", huge, &options, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "340282366920938463463374607431768211465\n"
+        );
+        assert!(run("u64", "sideways").is_err());
+
+        let sum = "18446744073709551615 1\n+                    +";
+        assert_eq!(
+            Day06.part1(sum).unwrap_err().to_string(),
+            "grand total: u64 overflow"
+        );
+        let too_big = Problem {
+            cells: vec!["10", "4000000000"],
+            operator: Operator::Power,
+        };
+        assert_eq!(
+            too_big.resolve::<BigUint>(Reading::PART1),
+            Err("result too large to evaluate".to_string())
+        );
+    }
+
+    #[test]
+    fn columns_from_separators() {
+        let numbers = |input: &str| -> Vec<Vec<String>> {
+            parse_input(input)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.cells.iter().map(|c| c.to_string()).collect())
+                .collect()
+        };
+        let cells = |cells: &[&str]| cells.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        // the last column is wider than the one before it
+        assert_eq!(
+            numbers("1 2\n3 456\n+ *"),
+            vec![cells(&["1", "3"]), cells(&["2", "456"])]
+        );
+        // operators not at the start of their column
+        assert_eq!(
+            numbers("12  3\n 4 56\n +  *"),
+            vec![cells(&["12", " 4"]), cells(&[" 3", "56"])]
+        );
+        // ragged line ends, and an operator wider than its numbers
+        assert_eq!(
+            numbers("7    10 3\n8\n9    5\nmax  +  *"),
+            vec![
+                cells(&["7  ", "8", "9  "]),
+                cells(&["10", "", "5"]),
+                cells(&["3", "", ""])
+            ]
+        );
+        assert_eq!(
+            Day06.part1("7    10 3\n8\n9    5\nmax  +  *").unwrap(),
+            "27"
+        );
+        assert_eq!(Day06.part1("1 2\n3 456\n+ *").unwrap(), "916");
+        assert_eq!(Day06.part1("12  3\n 4 56\n +  *").unwrap(), "184");
+    }
+
+    #[test]
+    fn readings() {
+        let problem = &parse_input(INPUT).unwrap()[0];
+        let read = |name: &str| -> Vec<String> {
+            problem
+                .operands(name.parse().unwrap())
+                .map(|digits| digits.to_string())
+                .collect()
+        };
+        // 123
+        //  45
+        //   6
+        assert_eq!(read("part1"), vec!["123", "45", "6"]);
+        assert_eq!(read("part2"), vec!["356", "24", "1"]);
+        assert_eq!(read("cephalopod-up"), vec!["653", "42", "1"]);
+        assert_eq!(read("columns"), vec!["1", "24", "356"]);
+        assert_eq!(read("columns-up"), vec!["1", "42", "653"]);
+        assert_eq!(read("diagonal"), vec!["146", "25", "3"]);
+        assert!("sideways".parse::<Reading>().is_err());
+
+        let total = |reading: &str| {
+            let options = Options::parse(["--reading", reading]).unwrap();
+            let mut out = Vec::new();
+            Day06.run("total", INPUT, &options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(total("normal"), "4277556\n");
+        assert_eq!(total("cephalopod"), "3263827\n");
+        // + and * do not care about the order of their operands
+        assert_eq!(total("columns"), "3263827\n");
+        // 1 * 42 * 653 + 963 + 842 + 8 + 23 * 185 * 571 + 326 + 134 + 4
+        assert_eq!(total("columns-up"), "2459308\n");
+    }
+
+    #[test]
+    fn explain_test() {
+        let explain = |args: &[&str]| {
+            let options = Options::parse(args.iter().copied()).unwrap();
+            let mut out = Vec::new();
+            Day06.run("explain", INPUT, &options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(
+            explain(&[]),
+            "normal reading:
+123 * 45 * 6 = 33210
+328 + 64 + 98 = 490
+51 * 387 * 215 = 4243455
+64 + 23 + 314 = 401
+grand total: 4277556
+cephalopod reading:
+356 * 24 * 1 = 8544
+8 + 248 + 369 = 625
+175 * 581 * 32 = 3253600
+4 + 431 + 623 = 1058
+grand total: 3263827
+"
+        );
+        assert!(
+            explain(&["--reading", "diagonal", "--int", "big"]).starts_with(
+                "diagonal reading:
+146 * 25 * 3 = 10950
+"
+            )
+        );
+
+        let options = Options::parse(["--int", "u64"]).unwrap();
+        let mut out = Vec::new();
+        let overflow = "4294967296 1\n4294967296 2\n*          +";
+        let err = Day06.run("explain", overflow, &options, &mut out);
+        assert_eq!(err.unwrap_err().to_string(), "problem 1: u64 overflow");
+        assert_eq!(String::from_utf8(out).unwrap(), "normal reading:\n");
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let export = |format: &str| {
+            let options = Options::parse(["--format", format]).unwrap();
+            let mut out = Vec::new();
+            Day06.run("export", INPUT, &options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let csv = export("csv");
+        assert_eq!(
+            csv,
+            "operator,result,operand 1,operand 2,operand 3
+*,33210,123,45,6
++,490,328,64,98
+*,4243455,51,387,215
++,401,64,23,314
+"
+        );
+        assert_eq!(export("tsv"), csv.replace(',', "\t"));
+
+        let operands = |problems: &[Problem]| -> Vec<(Vec<String>, Operator)> {
+            problems
+                .iter()
+                .map(|p| {
+                    let operands = p.rows().map(str::to_string).collect();
+                    (operands, p.operator.clone())
+                })
+                .collect()
+        };
+        let worksheet = render(&import(&csv, ',').unwrap());
+        assert_eq!(
+            worksheet,
+            "123 328  51  64\n 45  64 387  23\n  6  98 215 314\n*   +   *   +\n"
+        );
+        assert_eq!(
+            operands(&parse_input(&worksheet).unwrap()),
+            operands(&parse_input(INPUT).unwrap())
+        );
+
+        // ragged rows, long operators and problems without operands
+        let table = "min\t\t7\t12\n\n+\n^\t\t2\t\t100\t\n";
+        let problems = import(table, '\t').unwrap();
+        let worksheet = render(&problems);
+        assert_eq!(worksheet, "  7     2\n 12   100\nmin + ^\n");
+        assert_eq!(
+            operands(&parse_input(&worksheet).unwrap()),
+            operands(&problems)
+        );
+    }
+
+    #[test]
+    fn import_errors() {
+        let error = |table| import(table, ',').unwrap_err().to_string();
+        assert_eq!(
+            error("operator,result\n+,3,1,2\nplus,3,1,2"),
+            "'plus' at line 3, column 1: unknown operator (expected + * - / % ^ min max)"
+        );
+        assert_eq!(
+            error("+,,1, -2"),
+            "'-2' at line 1, column 7: operands must be written in digits"
+        );
+    }
+
+    #[test]
+    fn non_ascii_is_rejected() {
+        // a byte is a column: other text could not be lined up reliably
+        assert_eq!(
+            parse_input("1 2\n3 4\n+ ×").unwrap_err().to_string(),
+            "'×' at line 3, column 3: not ASCII, so columns may not line up"
+        );
+        assert_eq!(
+            Day06.part2("12 é\n3  4\n+  *").unwrap_err().to_string(),
+            "'é' at line 1, column 4: not ASCII, so columns may not line up"
+        );
+        assert_eq!(
+            Day06.part1("a  12\n    3\n+   *").unwrap_err().to_string(),
+            "problem 1: 'a' is not a number"
+        );
+    }
+
+    #[test]
+    fn column_operator_errors() {
+        assert_eq!(
+            parse_input("1 2\n3 4\n+").unwrap_err().to_string(),
+            "'2' at line 3, column 3: no operator under this column"
+        );
+        assert_eq!(
+            parse_input("12\n34\n+*").unwrap_err().to_string(),
+            "'+*' at line 3, column 1: unknown operator (expected + * - / % ^ min max)"
+        );
+        assert_eq!(
+            parse_input("123\n456\n+ *").unwrap_err().to_string(),
+            "'*' at line 3, column 3: more than one operator in this column"
+        );
+    }
+
+    #[test]
+    fn unknown_operator_is_located() {
+        assert_eq!(
+            parse_input("1 2\n3 4\n\n+ && *"),
+            Err(ParseError {
+                line: 4,
+                column: 3,
+                text: "&&".to_string(),
+                reason: "unknown operator (expected + * - / % ^ min max)",
+            })
+        );
     }
 
     #[test]
     fn convert_to_cephalopod_math_test() {
+        let read = |problem: &Problem| -> Vec<String> {
+            let operands = problem.operands(Reading::PART2);
+            operands.map(|digits| digits.to_string()).collect()
+        };
         let p1: Problem = Problem {
-            numbers: vec!["64 ".to_string(), "23 ".to_string(), "314".to_string()],
+            cells: vec!["64 ", "23 ", "314"],
             operator: Operator::Multiply,
         };
-        assert_eq!(
-            p1.convert_to_cephalopod_math(),
-            Problem {
-                numbers: vec!["623".to_string(), "431".to_string(), "  4".to_string()],
-                operator: Operator::Multiply,
-            }
-        );
+        assert_eq!(read(&p1), vec!["4", "431", "623"]);
         let p2 = Problem {
-            numbers: vec![" 51".to_string(), "387".to_string(), "215".to_string()],
+            cells: vec![" 51", "387", "215"],
+            operator: Operator::Add,
+        };
+        assert_eq!(read(&p2), vec!["175", "581", "32"]);
+        assert_eq!(p2.resolve::<u64>(Reading::PART2), Ok(788));
+        // digits split by a space are not one number
+        let split = Problem {
+            cells: vec!["1 ", "  ", "2 "],
             operator: Operator::Add,
         };
         assert_eq!(
-            p2.convert_to_cephalopod_math(),
-            Problem {
-                numbers: vec![" 32".to_string(), "581".to_string(), "175".to_string()],
-                operator: Operator::Add,
-            }
+            split.resolve::<u64>(Reading::PART2),
+            Err("'1 2' is not a number".to_string())
         );
     }
 
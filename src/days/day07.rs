@@ -1,5 +1,7 @@
-use crate::{AocResult, Day};
-use std::fmt;
+use crate::big::BigUint;
+use crate::{AocResult, Day, Options};
+use std::fmt::{self, Display};
+use std::io::Write;
 
 pub struct Day07;
 impl Day for Day07 {
@@ -8,44 +10,156 @@ impl Day for Day07 {
     }
 
     fn part1(&self, input: &str) -> AocResult<String> {
-        let mut grid = parse_input(input);
-        let (start_x, start_y) = grid.find_start();
-
-        let first = grid.add_tachyon(start_x, start_y + 1);
-        let other = ((start_y + 2)..grid.rows())
-            .map(|y| grid.propagate_tachyon_to_row(y))
-            .sum::<i32>();
-        println!("{}", grid);
-        Ok(format!("{}", first + other))
+        let beams = parse_input(input).propagate(())?;
+        Ok(beams.splits.to_string())
     }
 
     fn part2(&self, input: &str) -> AocResult<String> {
-        let table = input.trim().as_bytes();
-        let columns = table
-            .iter()
-            .position(|&tile| tile == b'\n')
-            .expect("Invalid input");
-
-        let mut state = [1u64; 200];
-        for row in table.chunks(columns + 1).rev() {
-            let mut new_state = [0; 200];
-            for (c, &tile) in row.iter().take(columns).enumerate() {
-                if tile == b'^' {
-                    new_state[c] = c.checked_sub(1).map_or(0, |c| state[c]) + {
-                        let c = c + 1;
-                        if c < columns { state[c] } else { 0 }
-                    };
-                } else if tile == b'S' {
-                    return Ok(state[c].to_string());
-                } else {
-                    new_state[c] = state[c];
-                }
+        let beams = parse_input(input).propagate(1u64)?;
+        Ok(beams.timelines.to_string())
+    }
+
+    fn run(
+        &self,
+        command: &str,
+        input: &str,
+        options: &Options,
+        out: &mut dyn Write,
+    ) -> AocResult<()> {
+        if command != "timelines" {
+            return Err(
+                format!("day07 has no command '{}' (available: timelines)", command).into(),
+            );
+        }
+        options.allow(&["int", "modulo"])?;
+        let tree = parse_input(input);
+        let timelines = match (options.get::<u64>("modulo")?, options.value("int")) {
+            (Some(_), Some(_)) => return Err("--modulo and --int exclude each other".into()),
+            (Some(0), None) => return Err("--modulo must be at least 1".into()),
+            (Some(modulus), None) => {
+                let one = Modulo {
+                    residue: 1 % modulus,
+                    modulus,
+                };
+                tree.propagate(one)?.timelines.to_string()
             }
+            (None, int) => match int.unwrap_or("u64") {
+                "u64" => tree.propagate(1u64)?.timelines.to_string(),
+                "u128" => tree.propagate(1u128)?.timelines.to_string(),
+                "big" => tree.propagate(BigUint::from(1u64))?.timelines.to_string(),
+                other => {
+                    return Err(format!(
+                        "unknown integer type: {} (expected u64, u128 or big)",
+                        other
+                    )
+                    .into());
+                }
+            },
+        };
+        writeln!(out, "{}", timelines)?;
+        Ok(())
+    }
+}
+
+/// Where the beam went on its way down the manifold.
+#[derive(Debug, PartialEq)]
+struct Beams<C> {
+    /// The splitters hit by at least one beam.
+    splits: u64,
+    /// The ways down from the start through any choice of side at every
+    /// splitter to the bottom. Beams split off a side are lost, and not
+    /// counted.
+    timelines: C,
+}
+
+/// A type timelines can be counted in.
+trait Count: Clone {
+    /// Why a count does not fit.
+    const OVERFLOW: &'static str;
+
+    /// Zero, modulo the same number as `self` where there is one.
+    fn zero(&self) -> Self;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+/// Where the beams are, without counting timelines.
+impl Count for () {
+    const OVERFLOW: &'static str = "unreachable: nothing is counted";
+
+    fn zero(&self) -> Self {}
+
+    fn checked_add(&self, _: &Self) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl Count for u64 {
+    const OVERFLOW: &'static str = "timelines overflow u64 (try --int u128 or --int big)";
+
+    fn zero(&self) -> Self {
+        0
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+}
+
+impl Count for u128 {
+    const OVERFLOW: &'static str = "timelines overflow u128 (try --int big)";
+
+    fn zero(&self) -> Self {
+        0
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u128::checked_add(*self, *other)
+    }
+}
+
+impl Count for BigUint {
+    const OVERFLOW: &'static str = "unreachable: big integers do not overflow";
+
+    fn zero(&self) -> Self {
+        BigUint::zero()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self.clone() + other)
+    }
+}
+
+/// A count known only modulo `modulus` (a prime, say), for manifolds whose
+/// exact count is too long to check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Modulo {
+    residue: u64,
+    modulus: u64,
+}
+
+impl Count for Modulo {
+    const OVERFLOW: &'static str = "unreachable: residues do not overflow";
 
-            state = new_state;
+    fn zero(&self) -> Self {
+        Modulo {
+            residue: 0,
+            modulus: self.modulus,
         }
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        let sum = (self.residue as u128 + other.residue as u128) % self.modulus as u128;
+        Some(Modulo {
+            residue: sum as u64,
+            modulus: self.modulus,
+        })
+    }
+}
 
-        unreachable!()
+impl Display for Modulo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.residue)
     }
 }
 
@@ -54,18 +168,6 @@ enum Quadrant {
     Start,
     Empty,
     Splitter,
-    Tachyon,
-}
-impl Quadrant {
-    fn matches(&self, q: Quadrant) -> bool {
-        match (self, q) {
-            (Quadrant::Empty, Quadrant::Empty)
-            | (Quadrant::Start, Quadrant::Start)
-            | (Quadrant::Splitter, Quadrant::Splitter)
-            | (Quadrant::Tachyon, Quadrant::Tachyon) => true,
-            (_, _) => false,
-        }
-    }
 }
 impl fmt::Display for Quadrant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -73,7 +175,6 @@ impl fmt::Display for Quadrant {
             Quadrant::Start => write!(f, "A"),
             Quadrant::Empty => write!(f, "E"),
             Quadrant::Splitter => write!(f, "S"),
-            Quadrant::Tachyon => write!(f, "T"),
         }
     }
 }
@@ -92,52 +193,64 @@ struct InputTree {
     quadrants: Vec<Vec<Quadrant>>,
 }
 impl InputTree {
-    fn rows(&self) -> usize {
-        self.quadrants.len()
-    }
-
-    fn find_start(&self) -> (usize, usize) {
-        self.quadrants
-            .iter()
-            .enumerate()
-            .find_map(|(y, row)| {
-                row.iter()
-                    .enumerate()
-                    .find_map(|(x, q)| (q == &Quadrant::Start).then(|| (x, y)))
-            })
-            .unwrap()
-    }
-
-    fn propagate_tachyon_to_row(&mut self, y: usize) -> i32 {
-        self.clone()
-            .find_tachyons_in_row(y - 1)
-            .iter()
-            .map(|x| self.add_tachyon(*x, y))
-            .sum()
+    fn find_start(&self) -> Option<(usize, usize)> {
+        self.quadrants.iter().enumerate().find_map(|(y, row)| {
+            row.iter()
+                .position(|q| q == &Quadrant::Start)
+                .map(|x| (x, y))
+        })
     }
 
-    fn find_tachyons_in_row(self, y: usize) -> Vec<usize> {
-        self.quadrants[y]
-            .iter()
-            .enumerate()
-            .filter_map(|(x, q)| q.matches(Quadrant::Tachyon).then(|| x))
-            .collect()
-    }
+    /// Sends the beam down from the start one row at a time, keeping only
+    /// the number of timelines in every column of the current row (`None`
+    /// where no beam is), counted from `one` for the start. A beam on a
+    /// splitter goes on from the columns beside it in the next row; one split
+    /// off a side is lost.
+    fn propagate<C: Count>(&self, one: C) -> AocResult<Beams<C>> {
+        let (start_x, start_y) = self.find_start().ok_or("no start (S) in the manifold")?;
+        let width = self.quadrants.iter().map(Vec::len).max().unwrap_or(0);
+        let zero = one.zero();
 
-    fn add_tachyon(&mut self, x: usize, y: usize) -> i32 {
-        match self.quadrants[y][x] {
-            Quadrant::Empty => {
-                self.quadrants[y][x] = Quadrant::Tachyon;
-                0
-            }
-            Quadrant::Splitter => {
-                self.quadrants[y][x - 1] = Quadrant::Tachyon;
-                self.quadrants[y][x + 1] = Quadrant::Tachyon;
-                1
+        let mut timelines: Vec<Option<C>> = vec![None; width];
+        let mut next = vec![None; width];
+        timelines[start_x] = Some(one);
+        let mut splits = 0;
+        for (y, row) in self.quadrants.iter().enumerate().skip(start_y + 1) {
+            let add = |to: &mut Option<C>, count: &C| -> AocResult<()> {
+                let sum = match to {
+                    Some(to) => to.checked_add(count),
+                    None => Some(count.clone()),
+                };
+                *to = Some(sum.ok_or_else(|| format!("line {}: {}", y + 1, C::OVERFLOW))?);
+                Ok(())
+            };
+            next.fill(None);
+            for (x, count) in timelines.iter().enumerate() {
+                let Some(count) = count else { continue };
+                if row.get(x) == Some(&Quadrant::Splitter) {
+                    splits += 1;
+                    if let Some(left) = x.checked_sub(1) {
+                        add(&mut next[left], count)?;
+                    }
+                    if let Some(right) = next.get_mut(x + 1) {
+                        add(right, count)?;
+                    }
+                } else {
+                    add(&mut next[x], count)?;
+                }
             }
-            Quadrant::Tachyon => 0,
-            q => panic!("quadrant in unexpected state at ({}, {}) -> {}", x, y, q),
+            std::mem::swap(&mut timelines, &mut next);
         }
+
+        let total = timelines.iter().flatten().try_fold(zero, |total, count| {
+            total
+                .checked_add(count)
+                .ok_or_else(|| format!("total: {}", C::OVERFLOW))
+        })?;
+        Ok(Beams {
+            splits,
+            timelines: total,
+        })
     }
 }
 impl fmt::Display for InputTree {
@@ -161,13 +274,7 @@ impl fmt::Display for InputTree {
 fn parse_input(input: &str) -> InputTree {
     let tree = input
         .lines()
-        .into_iter()
-        .map(|s| {
-            s.chars()
-                .into_iter()
-                .map(|c| Quadrant::from(c))
-                .collect::<Vec<_>>()
-        })
+        .map(|s| s.chars().map(Quadrant::from).collect::<Vec<_>>())
         .collect();
     InputTree { quadrants: tree }
 }
@@ -200,16 +307,105 @@ mod tests {
         assert_eq!(count(&result, Quadrant::Start), 1);
         assert_eq!(count(&result, Quadrant::Splitter), 22);
         assert_eq!(count(&result, Quadrant::Empty), 217);
-        assert_eq!(count(&result, Quadrant::Tachyon), 0);
     }
 
     fn count(tree: &InputTree, quadrant: Quadrant) -> usize {
         tree.quadrants
             .iter()
-            .map(|row| row.iter().filter(|q| q.matches(quadrant)).count())
+            .map(|row| row.iter().filter(|&&q| q == quadrant).count())
             .sum()
     }
 
+    #[test]
+    fn propagate_test() {
+        let beams = parse_input(INPUT).propagate(1u64).unwrap();
+        assert_eq!(
+            beams,
+            Beams {
+                splits: 21,
+                timelines: 40
+            }
+        );
+
+        // wider than any fixed buffer, with the start past column 200
+        let wide = format!(
+            "{0}S{1}\n{0}.{1}\n{0}^{1}\n{0}.{1}",
+            ".".repeat(250),
+            ".".repeat(50)
+        );
+        let beams = parse_input(&wide).propagate(1u64).unwrap();
+        assert_eq!((beams.splits, beams.timelines), (1, 2));
+
+        // a beam split off the left side is lost, and one split next to a
+        // splitter goes on below it
+        let beams = parse_input("S..\n^^.\n...\n.^.").propagate(1u64).unwrap();
+        assert_eq!((beams.splits, beams.timelines), (2, 2));
+
+        assert!(parse_input("...\n.^.").propagate(1u64).is_err());
+    }
+
+    /// A start above `depth` rows of splitters only, wide enough for no
+    /// beam to leave: every row doubles the timelines.
+    fn doubling(depth: usize) -> String {
+        let side = ".".repeat(depth + 1);
+        let splitters = "^".repeat(2 * depth + 3);
+        let mut manifold = format!("{side}S{side}\n");
+        for _ in 0..depth {
+            manifold.push_str(&splitters);
+            manifold.push('\n');
+        }
+        manifold
+    }
+
+    #[test]
+    fn exact_and_modular_counts() {
+        let timelines = |manifold: &str, args: &[&str]| {
+            let options = Options::parse(args.iter().copied()).unwrap();
+            let mut out = Vec::new();
+            Day07
+                .run("timelines", manifold, &options, &mut out)
+                .map(|()| String::from_utf8(out).unwrap())
+                .map_err(|e| e.to_string())
+        };
+        let deep = doubling(70);
+        assert_eq!(Day07.part1(&deep).unwrap(), "2485");
+        assert_eq!(
+            Day07.part2(&deep).unwrap_err().to_string(),
+            "line 69: timelines overflow u64 (try --int u128 or --int big)"
+        );
+        assert_eq!(
+            timelines(&deep, &["--int", "u128"]),
+            Ok("1180591620717411303424\n".to_string())
+        );
+        assert_eq!(
+            timelines(&deep, &["--modulo", "1000000007"]),
+            Ok("270016253\n".to_string())
+        );
+
+        let deeper = doubling(130);
+        assert!(timelines(&deeper, &["--int", "u128"]).is_err());
+        assert_eq!(
+            timelines(&deeper, &["--int", "big"]),
+            Ok("1361129467683753853853498429727072845824\n".to_string())
+        );
+        assert_eq!(
+            timelines(&deeper, &["--modulo", "1000000007"]),
+            Ok("118529101\n".to_string())
+        );
+        // residues of 0 still carry their beams to the splitters below
+        let beams = parse_input(&deep)
+            .propagate(Modulo {
+                residue: 1,
+                modulus: 2,
+            })
+            .unwrap();
+        assert_eq!((beams.splits, beams.timelines.residue), (2485, 0));
+
+        assert_eq!(timelines(INPUT, &[]), Ok("40\n".to_string()));
+        assert!(timelines(INPUT, &["--modulo", "0"]).is_err());
+        assert!(timelines(INPUT, &["--modulo", "7", "--int", "big"]).is_err());
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(Day07.part1(INPUT).unwrap(), "21");
@@ -0,0 +1,48 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// Input that could not be parsed: the offending text, where it is (both
+/// numbered from 1, columns counted in chars) and why it was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub reason: &'static str,
+}
+
+impl ParseError {
+    /// The error for `text`, found at byte `offset` of `line`, which is line
+    /// `row` of the input counted from 0.
+    pub fn new(row: usize, line: &str, offset: usize, text: &str, reason: &'static str) -> Self {
+        ParseError {
+            line: row + 1,
+            column: line[..offset].chars().count() + 1,
+            text: text.to_string(),
+            reason,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' at line {}, column {}: {}",
+            self.text, self.line, self.column, self.reason
+        )
+    }
+}
+impl Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_count_chars() {
+        let error = ParseError::new(2, "é-x", 3, "x", "not a number");
+        assert_eq!((error.line, error.column), (3, 3));
+        assert_eq!(error.to_string(), "'x' at line 3, column 3: not a number");
+    }
+}
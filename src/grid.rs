@@ -0,0 +1,461 @@
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+/// The eight cells around a cell, as `(row, col)` offsets.
+pub const MOORE: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// A rectangular grid stored row by row in a single `Vec`. Cells are
+/// addressed either by `(row, col)` or by their index in that `Vec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone + Default> Grid<T> {
+    /// One row per line, one cell per char. Lines shorter than the longest one
+    /// are padded with `T::default()`.
+    pub fn parse(input: &str, convert: impl Fn(char) -> T) -> Self {
+        let lines: Vec<&str> = input.lines().collect();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for line in &lines {
+            let start = cells.len();
+            cells.extend(line.chars().map(&convert));
+            cells.resize(start + width, T::default());
+        }
+
+        Grid {
+            width,
+            height: lines.len(),
+            cells,
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        (row < self.height && col < self.width).then(|| &self.cells[row * self.width + col])
+    }
+
+    /// A grid of the same shape with `f` applied to every cell.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    pub fn position(&self, index: usize) -> (usize, usize) {
+        (index / self.width, index % self.width)
+    }
+
+    /// The indices of the cells at `offsets` from `index` that lie inside the
+    /// grid.
+    pub fn neighbours<'a>(
+        &'a self,
+        index: usize,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.neighbours_with(index, offsets, Boundary::Empty)
+            .filter_map(|n| match n {
+                Neighbour::Cell(j) => Some(j),
+                Neighbour::Outside => None,
+            })
+    }
+
+    /// The cells at `offsets` from `index`, with positions past the edge
+    /// resolved by `boundary`.
+    pub fn neighbours_with<'a>(
+        &'a self,
+        index: usize,
+        offsets: &'a [(isize, isize)],
+        boundary: Boundary,
+    ) -> impl Iterator<Item = Neighbour> + 'a {
+        Shape::of(self.width, self.height).neighbours_with(index, offsets, boundary)
+    }
+
+    /// The cells that have `index` among their `neighbours_with`, once for
+    /// every offset through which they reach it.
+    pub fn counted_by<'a>(
+        &'a self,
+        index: usize,
+        offsets: &'a [(isize, isize)],
+        boundary: Boundary,
+    ) -> impl Iterator<Item = usize> + 'a {
+        Shape::of(self.width, self.height).counted_by(index, offsets, boundary)
+    }
+}
+
+/// The width and height of a grid, for the neighbour queries shared by
+/// `Grid` and `BitGrid`. Cells are numbered row by row from 0.
+#[derive(Debug, Clone, Copy)]
+struct Shape {
+    width: usize,
+    height: usize,
+}
+impl Shape {
+    fn of(width: usize, height: usize) -> Self {
+        Shape { width, height }
+    }
+
+    fn neighbours_with(
+        self,
+        index: usize,
+        offsets: &[(isize, isize)],
+        boundary: Boundary,
+    ) -> impl Iterator<Item = Neighbour> + '_ {
+        let (row, col) = (index / self.width, index % self.width);
+        offsets.iter().filter_map(move |&(dr, dc)| {
+            let r = boundary.resolve(row as isize + dr, self.height);
+            let c = boundary.resolve(col as isize + dc, self.width);
+            match (r, c) {
+                (Some(r), Some(c)) => Some(Neighbour::Cell(r * self.width + c)),
+                _ if boundary == Boundary::Occupied => Some(Neighbour::Outside),
+                _ => None,
+            }
+        })
+    }
+
+    fn counted_by(
+        self,
+        index: usize,
+        offsets: &[(isize, isize)],
+        boundary: Boundary,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let (row, col) = (index / self.width, index % self.width);
+        offsets.iter().flat_map(move |&(dr, dc)| {
+            let cols = boundary.sources(col, dc, self.width);
+            boundary
+                .sources(row, dr, self.height)
+                .into_iter()
+                .flatten()
+                .flat_map(move |r| cols.into_iter().flatten().map(move |c| r * self.width + c))
+        })
+    }
+}
+
+/// What lies past the edges of a grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// Nothing: positions outside are skipped.
+    Empty,
+    /// Positions outside are reported as `Neighbour::Outside`.
+    Occupied,
+    /// The grid repeats, as on a torus.
+    Wrap,
+    /// The grid is reflected at its edges: the row above the first one is the
+    /// first one again.
+    Mirror,
+}
+impl Boundary {
+    /// Where coordinate `x` lands on an axis of length `n`, if on the grid.
+    fn resolve(self, x: isize, n: usize) -> Option<usize> {
+        let n = n as isize;
+        match self {
+            Boundary::Empty | Boundary::Occupied => (0..n).contains(&x).then_some(x as usize),
+            Boundary::Wrap => Some(x.rem_euclid(n) as usize),
+            Boundary::Mirror => {
+                let x = x.rem_euclid(2 * n);
+                Some(if x < n { x } else { 2 * n - 1 - x } as usize)
+            }
+        }
+    }
+
+    /// The coordinates `y` on an axis of length `n` for which `y + delta`
+    /// resolves to `target`: at most one per period of the boundary.
+    fn sources(self, target: usize, delta: isize, n: usize) -> [Option<usize>; 2] {
+        let (target, n) = (target as isize, n as isize);
+        match self {
+            Boundary::Empty | Boundary::Occupied => {
+                let y = target - delta;
+                [(0..n).contains(&y).then_some(y as usize), None]
+            }
+            Boundary::Wrap => [Some((target - delta).rem_euclid(n) as usize), None],
+            Boundary::Mirror => [target - delta, 2 * n - 1 - target - delta].map(|y| {
+                let y = y.rem_euclid(2 * n);
+                (y < n).then_some(y as usize)
+            }),
+        }
+    }
+}
+impl FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "empty" => Ok(Boundary::Empty),
+            "occupied" => Ok(Boundary::Occupied),
+            "wrap" => Ok(Boundary::Wrap),
+            "mirror" => Ok(Boundary::Mirror),
+            _ => Err(format!("unknown boundary: {}", s)),
+        }
+    }
+}
+
+/// A position reached from a cell: a cell of the grid, or the outside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbour {
+    Cell(usize),
+    Outside,
+}
+
+impl<T> Index<usize> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.cells[index]
+    }
+}
+
+impl<T> IndexMut<usize> for Grid<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.cells[index]
+    }
+}
+
+/// A grid of booleans packed 64 cells to a `u64`, row by row. Each row starts
+/// on a new word, and the bits past the last column are always zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(64);
+        BitGrid {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    /// One row per line, a cell set wherever `is_set` holds. Lines shorter
+    /// than the longest one are padded with unset cells.
+    pub fn parse(input: &str, is_set: impl Fn(char) -> bool) -> Self {
+        let width = input.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut bits = BitGrid::new(width, input.lines().count());
+        for (row, line) in input.lines().enumerate() {
+            for (col, _) in line.chars().enumerate().filter(|&(_, c)| is_set(c)) {
+                bits.set(row, col, true);
+            }
+        }
+        bits
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn words_per_row(&self) -> usize {
+        self.words_per_row
+    }
+
+    /// The words of `row`; bit `c % 64` of word `c / 64` is column `c`.
+    pub fn row(&self, row: usize) -> &[u64] {
+        &self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        row < self.height
+            && col < self.width
+            && self.words[row * self.words_per_row + col / 64] >> (col % 64) & 1 == 1
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: bool) {
+        assert!(row < self.height && col < self.width);
+        let word = &mut self.words[row * self.words_per_row + col / 64];
+        if value {
+            *word |= 1 << (col % 64);
+        } else {
+            *word &= !(1 << (col % 64));
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// The indices (`row * width + col`, as in `Grid`) of the set cells, in
+    /// reading order, skipping 64 unset cells at a time.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(move |(w, &word)| {
+            let (row, first) = (w / self.words_per_row, w % self.words_per_row * 64);
+            let mut bits = word;
+            std::iter::from_fn(move || {
+                (bits != 0).then(|| {
+                    let col = first + bits.trailing_zeros() as usize;
+                    bits &= bits - 1;
+                    row * self.width + col
+                })
+            })
+        })
+    }
+
+    /// `get` by index (`row * width + col`).
+    pub fn at(&self, index: usize) -> bool {
+        self.get(index / self.width, index % self.width)
+    }
+
+    /// `set` by index (`row * width + col`).
+    pub fn set_at(&mut self, index: usize, value: bool) {
+        self.set(index / self.width, index % self.width, value);
+    }
+
+    /// As `Grid::neighbours_with`, by index.
+    pub fn neighbours_with<'a>(
+        &self,
+        index: usize,
+        offsets: &'a [(isize, isize)],
+        boundary: Boundary,
+    ) -> impl Iterator<Item = Neighbour> + 'a {
+        Shape::of(self.width, self.height).neighbours_with(index, offsets, boundary)
+    }
+
+    /// As `Grid::counted_by`, by index.
+    pub fn counted_by<'a>(
+        &self,
+        index: usize,
+        offsets: &'a [(isize, isize)],
+        boundary: Boundary,
+    ) -> impl Iterator<Item = usize> + 'a {
+        Shape::of(self.width, self.height).counted_by(index, offsets, boundary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pads_short_lines() {
+        let grid = Grid::parse("@.@\n@\n", |c| c == '@');
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.cells(), &[true, false, true, true, false, false]);
+        assert_eq!(grid.get(1, 0), Some(&true));
+        assert_eq!(grid.get(2, 0), None);
+    }
+
+    #[test]
+    fn neighbours_with_boundaries() {
+        let grid = Grid::parse("abc\ndef", |c| c);
+        let around_a = |boundary| -> Vec<_> { grid.neighbours_with(0, &MOORE, boundary).collect() };
+        let cells = |v: &[usize]| v.iter().map(|&i| Neighbour::Cell(i)).collect::<Vec<_>>();
+
+        assert_eq!(around_a(Boundary::Empty), cells(&[1, 3, 4]));
+        assert_eq!(around_a(Boundary::Occupied).len(), 8);
+        assert_eq!(
+            around_a(Boundary::Occupied)
+                .iter()
+                .filter(|n| **n == Neighbour::Outside)
+                .count(),
+            5
+        );
+        // rows wrap 0 -> 1 -> 0, columns 0 -> 2 -> 1
+        assert_eq!(around_a(Boundary::Wrap), cells(&[5, 3, 4, 2, 1, 5, 3, 4]));
+        // the cell itself is reflected back for the offsets leaving the grid
+        assert_eq!(around_a(Boundary::Mirror), cells(&[0, 0, 1, 0, 1, 3, 3, 4]));
+    }
+
+    #[test]
+    fn counted_by_inverts_neighbours() {
+        let grid = Grid::parse("....\n....\n....", |c| c);
+        let offsets = [(-1, 0), (0, 2), (1, 1), (2, -3), (-4, 5)];
+        for boundary in [
+            Boundary::Empty,
+            Boundary::Occupied,
+            Boundary::Wrap,
+            Boundary::Mirror,
+        ] {
+            for i in 0..grid.cells().len() {
+                let mut expected: Vec<usize> = (0..grid.cells().len())
+                    .flat_map(|j| {
+                        grid.neighbours_with(j, &offsets, boundary)
+                            .filter(move |&n| n == Neighbour::Cell(i))
+                            .map(move |_| j)
+                    })
+                    .collect();
+                let mut actual: Vec<usize> = grid.counted_by(i, &offsets, boundary).collect();
+                expected.sort();
+                actual.sort();
+                assert_eq!(actual, expected, "{:?} {}", boundary, i);
+            }
+        }
+    }
+
+    #[test]
+    fn bit_grid_get_set() {
+        let input = format!("{}\n{}@", "@".repeat(70), ".".repeat(64));
+        let mut bits = BitGrid::parse(&input, |c| c == '@');
+        assert_eq!(
+            (bits.width(), bits.height(), bits.words_per_row()),
+            (70, 2, 2)
+        );
+        assert_eq!(bits.row(0), &[u64::MAX, 0b111111]);
+        assert_eq!(bits.row(1), &[0, 1]);
+        assert!(bits.get(1, 64) && !bits.get(1, 63) && !bits.get(2, 0));
+
+        bits.set(0, 3, false);
+        assert_eq!(bits.count_ones(), 70);
+        assert!(bits.at(70 + 64) && !bits.at(3));
+        let ones: Vec<usize> = bits.ones().collect();
+        assert_eq!(ones.len(), 70);
+        assert_eq!(ones[..4], [0, 1, 2, 4]);
+        assert_eq!(ones[68..], [69, 134]);
+
+        bits.set_at(134, false);
+        assert!(!bits.get(1, 64));
+    }
+
+    #[test]
+    fn neighbours_stay_inside() {
+        let grid = Grid::parse("...\n...\n...", |c| c == '@');
+        let mut corner: Vec<_> = grid.neighbours(0, &MOORE).collect();
+        corner.sort();
+        assert_eq!(corner, vec![1, 3, 4]);
+        assert_eq!(grid.neighbours(4, &MOORE).count(), 8);
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use std::io::Write;
 
 pub type AocResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
@@ -6,10 +7,27 @@ pub trait Day {
     fn name(&self) -> &'static str; // es. "day01"
     fn part1(&self, input: &str) -> AocResult<String>;
     fn part2(&self, input: &str) -> AocResult<String>;
+
+    /// Runs a day-specific command other than the two parts (e.g. `explain`).
+    fn run(
+        &self,
+        command: &str,
+        _input: &str,
+        _options: &Options,
+        _out: &mut dyn Write,
+    ) -> AocResult<()> {
+        Err(format!("{} has no command '{}'", self.name(), command).into())
+    }
 }
 
+pub mod big;
+pub mod cli;
 pub mod days;
+pub mod error;
+pub mod grid;
+
+pub use cli::Options;
 
 pub fn registry() -> BTreeMap<u32, Box<dyn Day>> {
     days::registry()
-}
\ No newline at end of file
+}
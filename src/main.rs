@@ -1,34 +1,45 @@
 use std::{fs, path::PathBuf};
 
-use advent_of_code::{registry, AocResult};
+use advent_of_code::{AocResult, Options, registry};
 
-fn main() -> AocResult<()> {
-    let mut args = std::env::args().skip(1);
+const USAGE: &str = "Usage: cargo run -- <day> <part|command> [input_path] [--option value...]";
 
-    let day: u32 = args
-        .next()
-        .ok_or("Usage: cargo run -- <day> <part> [input_path]")?
-        .parse()?;
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> AocResult<()> {
+    let mut args = std::env::args().skip(1).peekable();
 
-    let part: u32 = args
-        .next()
-        .ok_or("Usage: cargo run -- <day> <part> [input_path]")?
-        .parse()?;
+    let day: u32 = args.next().ok_or(USAGE)?.parse()?;
 
-    let input_path: PathBuf = match args.next() {
+    let command = args.next().ok_or(USAGE)?;
+
+    let input_path: PathBuf = match args.next_if(|a| !a.starts_with("--")) {
         Some(p) => p.into(),
         None => format!("inputs/day{:02}.txt", day).into(),
     };
 
+    let options = Options::parse(args)?;
+
     let input = fs::read_to_string(&input_path)?;
 
     let reg = registry();
     let solver = reg.get(&day).ok_or("Unknown day")?;
 
-    let out = match part {
-        1 => solver.part1(&input)?,
-        2 => solver.part2(&input)?,
-        _ => return Err("Part must be 1 or 2".into()),
+    let out = match command.as_str() {
+        "1" | "2" => {
+            options.allow(&[])?;
+            if command == "1" {
+                solver.part1(&input)?
+            } else {
+                solver.part2(&input)?
+            }
+        }
+        other => return solver.run(other, &input, &options, &mut std::io::stdout().lock()),
     };
 
     println!("{}", out);